@@ -1,19 +1,60 @@
 
-pub fn first_puzzle() -> String
-{
-    let mut numbers: Vec<i32> = (0..256).collect();
-    let lengths = vec![187,254,0,81,169,219,1,190,19,102,255,56,46,32,2,216];
-    format!("{}", knot_sparse_hash(&mut numbers, &lengths))
-}
+use error::Error;
+use solution::Solution;
 
-pub fn second_puzzle() -> String
+pub struct Day10;
+
+impl Solution for Day10
 {
-    let mut numbers: Vec<i32> = (0..256).collect();
-    let lengths = lengths("187,254,0,81,169,219,1,190,19,102,255,56,46,32,2,216");
-    format!("{}", knot_dense_hash(&mut numbers, &lengths))
+    type Input = String;
+
+    fn day(&self) -> u32
+    {
+        10
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Knot Hash"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "knot.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<String, Error>
+    {
+        let lengths = input.trim();
+        for token in lengths.split(',').map(|token| token.trim())
+        {
+            match token.parse::<usize>()
+            {
+                Ok(length) if length <= 256 => {},
+                _ => return Err(Error::at_token(1, input, token, format!("invalid length: {}", token)))
+            }
+        }
+        Ok(String::from(lengths))
+    }
+
+    fn first_puzzle(&self, input: &String) -> Result<String, Error>
+    {
+        let mut numbers: Vec<i32> = (0..256).collect();
+        let lengths = input.split(',')
+                           .filter_map(|token| token.trim().parse::<usize>().ok())
+                           .collect::<Vec<_>>();
+        Ok(format!("{}", knot_sparse_hash(&mut numbers, &lengths)))
+    }
+
+    fn second_puzzle(&self, input: &String) -> Result<String, Error>
+    {
+        let mut numbers: Vec<i32> = (0..256).collect();
+        let lengths = lengths(input);
+        Ok(knot_dense_hash(&mut numbers, &lengths))
+    }
 }
 
-fn reverse_range(numbers: &mut Vec<i32>, start: usize, count: usize)
+fn reverse_range(numbers: &mut [i32], start: usize, count: usize)
 {
     let mut reversed: Vec<_> = numbers.iter()
                                       .cloned()
@@ -31,18 +72,18 @@ fn reverse_range(numbers: &mut Vec<i32>, start: usize, count: usize)
     }
 }
 
-fn tie_knot_round(mut numbers: &mut Vec<i32>, lengths: &Vec<usize>, curr_pos: &mut usize, skip: &mut usize)
+fn tie_knot_round(numbers: &mut [i32], lengths: &[usize], curr_pos: &mut usize, skip: &mut usize)
 {
     for len in lengths.iter()
     {
-        reverse_range(&mut numbers, *curr_pos, *len);
+        reverse_range(numbers, *curr_pos, *len);
         *curr_pos = (*curr_pos + *len + *skip) % numbers.len();
         *skip += 1;
     }
 }
 
 
-fn tie_knot(numbers: &mut Vec<i32>, lengths: &Vec<usize>)
+fn tie_knot(numbers: &mut [i32], lengths: &[usize])
 {
     let mut pos = 0;
     let mut skip = 0;
@@ -52,17 +93,17 @@ fn tie_knot(numbers: &mut Vec<i32>, lengths: &Vec<usize>)
     }
 }
 
-fn knot_sparse_hash(mut numbers: &mut Vec<i32>, lengths: &Vec<usize>) -> i32
+fn knot_sparse_hash(numbers: &mut [i32], lengths: &[usize]) -> i32
 {
-    tie_knot_round(&mut numbers, &lengths, &mut 0, &mut 0);
-    numbers.iter().take(2).fold(1, |acc, &el| acc * el)
+    tie_knot_round(numbers, lengths, &mut 0, &mut 0);
+    numbers.iter().take(2).product()
 }
 
-fn knot_dense_hash(mut numbers: &mut Vec<i32>, lengths: &Vec<usize>) -> String
+fn knot_dense_hash(numbers: &mut [i32], lengths: &[usize]) -> String
 {
-    tie_knot(&mut numbers, &lengths);
+    tie_knot(numbers, lengths);
     let mut hash = String::new();
-    for chunk in numbers.as_slice().chunks(16)
+    for chunk in numbers.chunks(16)
     {
         let val = chunk.iter().fold(0, |acc, el| acc ^ el);
         hash.push_str(&format!("{:02x}",val));
@@ -114,7 +155,7 @@ mod tests
     fn test_tie_knot() 
     {
         let mut numbers = vec![0, 1, 2, 3, 4];
-        tie_knot_round(&mut numbers, &vec![3, 4, 1, 5], &mut 0, &mut 0);
+        tie_knot_round(&mut numbers, &[3, 4, 1, 5], &mut 0, &mut 0);
         assert_eq!(numbers, vec![3, 4, 2, 1, 0]);
     }
 
@@ -1,41 +1,67 @@
-use std::i32;
-use std::io::prelude::*; 
-use std::fs::File; 
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let coord = read_path("path.txt");
-    format!("{}", coord.distance_from_origin())
-}
+pub struct Day11;
 
-pub fn second_puzzle() -> String
+impl Solution for Day11
 {
-    let mut file = File::open("path.txt").expect("Failed to open path file");
-    let mut content = String::new();
-    file.read_to_string(&mut content).expect("Failed to read path file");
-    let mut coord = HexagonCoord::origin();
-    let mut max_dist = 0i32;
-    for dir in content.split_terminator(',')
+    type Input = Vec<String>;
+
+    fn day(&self) -> u32
     {
-        coord.offset(dir.trim());
-        max_dist = i32::max(coord.distance_from_origin(), max_dist);
+        11
     }
 
-    format!("{}", max_dist)
-}
+    fn name(&self) -> &'static str
+    {
+        "Hex Ed"
+    }
 
-fn read_path(path: &str) -> HexagonCoord
-{
-    let mut file = File::open(path).expect("Failed to open path file");
-    let mut content = String::new();
-    file.read_to_string(&mut content).expect("Failed to read path file");
-    let mut coord = HexagonCoord::origin();
-    for dir in content.split_terminator(',')
+    fn input_path(&self) -> &'static str
+    {
+        "path.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<String>, Error>
+    {
+        read_path(input)
+    }
+
+    fn first_puzzle(&self, path: &Vec<String>) -> Result<String, Error>
+    {
+        let mut coord = HexagonCoord::origin();
+        for dir in path.iter()
+        {
+            coord.offset(dir);
+        }
+
+        Ok(format!("{}", coord.distance_from_origin()))
+    }
+
+    fn second_puzzle(&self, path: &Vec<String>) -> Result<String, Error>
     {
-        coord.offset(dir.trim());
+        let mut coord = HexagonCoord::origin();
+        let mut max_dist = 0i32;
+        for dir in path.iter()
+        {
+            coord.offset(dir);
+            max_dist = i32::max(coord.distance_from_origin(), max_dist);
+        }
+
+        Ok(format!("{}", max_dist))
     }
+}
 
-    coord
+fn read_path(input: &str) -> Result<Vec<String>, Error>
+{
+    input.split_terminator(',')
+           .map(|dir| dir.trim())
+           .map(|dir| match dir
+           {
+               "n" | "ne" | "nw" | "s" | "se" | "sw" => Ok(String::from(dir)),
+               _ => Err(Error::at_token(1, input, dir, format!("invalid direction: {}", dir)))
+           })
+           .collect()
 }
 
 struct HexagonCoord
@@ -1,19 +1,47 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::io::prelude::*; 
-use std::io::BufReader; 
-use std::fs::File; 
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let graph = Graph::from_file("connections.txt");
-    format!("{}", graph.connections(0).len())
-}
+pub struct Day12;
 
-pub fn second_puzzle() -> String
+impl Solution for Day12
 {
-    let graph = Graph::from_file("connections.txt");
-    format!("{}", graph.groups().len())
+    type Input = Graph;
+
+    fn day(&self) -> u32
+    {
+        12
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Digital Plumber"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "connections.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Graph, Error>
+    {
+        Graph::from_str(input)
+    }
+
+    fn first_puzzle(&self, graph: &Graph) -> Result<String, Error>
+    {
+        if !graph.nodes.contains_key(&0)
+        {
+            return Err(Error::no_solution("no program 0"));
+        }
+        Ok(format!("{}", graph.connections(0).len()))
+    }
+
+    fn second_puzzle(&self, graph: &Graph) -> Result<String, Error>
+    {
+        Ok(format!("{}", graph.groups().len()))
+    }
 }
 
 struct Node
@@ -29,22 +57,25 @@ impl Node
         Node {id, connections: Vec::new()}
     }
 
-    fn parse(record: &str) -> Node
+    fn parse(record: &str) -> Result<Node, Error>
     {
+        let id_of = |token: &str| token.parse::<u32>()
+                                       .map_err(|e| Error::at_token(1, record, token, format!("error parsing id {}: {}", token, e)));
         let mut iter = record.split("<->").map(|token| token.trim());
-        let token = iter.next().expect(&format!("Error reading id: {}", record));
-        let id = token.parse().expect(&format!("Error parsing id: {}", token));
+        let token = iter.next().unwrap_or(record);
+        let id = id_of(token)?;
         let mut node = Node::new(id);
-        let token = iter.next().expect(&format!("Error reading connections: {}", record));
+        let token = iter.next()
+                        .ok_or_else(|| Error::parse(1, record.len() + 1, format!("error reading connections: {}", record)))?;
         for conn in token.split(',').map(|token| token.trim())
         {
-           node.connections.push(conn.parse().expect(&format!("Error parsing connection id: {}", conn))); 
+           node.connections.push(id_of(conn)?); 
         }
-        node
+        Ok(node)
     }
 }
 
-struct Graph
+pub struct Graph
 {
     nodes: HashMap<u32, Node>
 }
@@ -74,9 +105,9 @@ impl Graph
     fn find_connections(self: &Self, id: u32, visited: &mut HashSet<u32>, connected: &mut HashSet<u32>)
     {
         visited.insert(id);
-        for conn_id in self.nodes.get(&id).unwrap().connections.iter()
+        for conn_id in self.nodes.get(&id).iter().flat_map(|node| node.connections.iter())
         {
-            if visited.contains(&conn_id)
+            if visited.contains(conn_id)
             {
                 continue;
             }
@@ -99,16 +130,22 @@ impl Graph
         groups
     }
 
-    fn from_file(path: &str) -> Graph
+    fn from_str(input: &str) -> Result<Graph, Error>
     {
         let mut graph = Graph::new();
-        let file = File::open(path).expect("Failed to open connections file"); 
-        let reader = BufReader::new(file); 
-        for line in reader.lines().filter_map(|res| res.ok())
+        for (idx, line) in input.lines().enumerate().filter(|&(_, l)| !l.trim().is_empty())
         {
-            graph.add(Node::parse(&line));
+            graph.add(Node::parse(line).map_err(|e| e.on_line(idx + 1))?);
         }
-        graph
+
+        for node in graph.nodes.values()
+        {
+            if let Some(conn) = node.connections.iter().find(|conn| !graph.nodes.contains_key(conn))
+            {
+                return Err(Error::invalid_state(format!("program {} connects to unknown program {}", node.id, conn)));
+            }
+        }
+        Ok(graph)
     }
 }
 
@@ -122,16 +159,22 @@ mod tests
     fn test_graph()
     {
         let mut graph = Graph::new();
-        graph.add(Node::parse("0 <-> 2"));
-        graph.add(Node::parse("1 <-> 1"));
-        graph.add(Node::parse("2 <-> 0, 3, 4"));
-        graph.add(Node::parse("3 <-> 2, 4"));
-        graph.add(Node::parse("4 <-> 2, 3, 6"));
-        graph.add(Node::parse("5 <-> 6"));
-        graph.add(Node::parse("6 <-> 4,5"));
+        graph.add(Node::parse("0 <-> 2").unwrap());
+        graph.add(Node::parse("1 <-> 1").unwrap());
+        graph.add(Node::parse("2 <-> 0, 3, 4").unwrap());
+        graph.add(Node::parse("3 <-> 2, 4").unwrap());
+        graph.add(Node::parse("4 <-> 2, 3, 6").unwrap());
+        graph.add(Node::parse("5 <-> 6").unwrap());
+        graph.add(Node::parse("6 <-> 4,5").unwrap());
         let cons = graph.connections(0);
         assert_eq!(cons.len(), 6);
         let groups = graph.groups();
         assert_eq!(groups.len(), 2);
+
+        for input in &["1 <-> 1", ""]
+        {
+            let graph = Day12.parse(input).unwrap();
+            assert_eq!(Day12.first_puzzle(&graph).unwrap_err().to_string(), "no solution: no program 0");
+        }
     }
 }
\ No newline at end of file
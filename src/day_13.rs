@@ -1,23 +1,50 @@
-use std::io::prelude::*; 
-use std::io::BufReader; 
-use std::fs::File; 
 use std::collections::HashMap;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let mut firewall = Firewall::from_file("firewall.txt");
-    format!("{}", firewall.severity())
-}
+pub struct Day13;
 
-pub fn second_puzzle() -> String
+impl Solution for Day13
 {
-    let mut firewall = Firewall::from_file("firewall.txt");
-    format!("{}", firewall.delay())
+    type Input = Firewall;
+
+    fn day(&self) -> u32
+    {
+        13
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Packet Scanners"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "firewall.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Firewall, Error>
+    {
+        Firewall::from_str(input)
+    }
+
+    fn first_puzzle(&self, firewall: &Firewall) -> Result<String, Error>
+    {
+        let mut firewall = firewall.clone();
+        Ok(format!("{}", firewall.severity()))
+    }
+
+    fn second_puzzle(&self, firewall: &Firewall) -> Result<String, Error>
+    {
+        Ok(format!("{}", firewall.delay()))
+    }
 }
 
+#[derive(Clone)]
 enum Dir { Up, Down}
 enum Caught {Yes(u32), No}
 
+#[derive(Clone)]
 struct Layer
 {
     range: u32,
@@ -48,7 +75,7 @@ impl Layer
                 self.scanner_pos -= 1;
             }
             (_, &Dir::Down)  =>  self.scanner_pos += 1,
-            (pos, &Dir::Up) if pos == 0 => {
+            (0, &Dir::Up) => {
                 self.scanner_dir = Dir::Down;
                 self.scanner_pos += 1;
             }
@@ -78,27 +105,37 @@ impl Layer
        let n = time % self.period;
        if n <= (self.period /2)
        {
-           return n
-       } 
+           n
+       }
+       else
        {
-           return self.period - n
+           self.period - n
        }
     }
 
-    fn parse(line: &str) -> Layer
+    fn parse(line: &str) -> Result<Layer, Error>
     {
         let mut iter = line.split(':').map(|token| token.trim());
-        let depth_token = iter.next().expect(&format!("Failed to read layer depth: {}", line));
-        let depth = depth_token.parse().expect(&format!("Failed to parse layerdepth: {}", depth_token));
-        let range_token = iter.next().expect(&format!("Failed to read layer range: {}", line));
-        let range = range_token.parse().expect(&format!("Failed to parse layer range: {}", range_token));
-        Layer::new(depth, range)
+        let depth_token = iter.next().unwrap_or(line);
+        let depth = depth_token.parse()
+                               .map_err(|e| Error::at_token(1, line, depth_token, format!("failed to parse layer depth {}: {}", depth_token, e)))?;
+        let range_token = iter.next()
+                              .ok_or_else(|| Error::parse(1, line.len() + 1, format!("failed to read layer range: {}", line)))?;
+        let range = range_token.parse()
+                               .map_err(|e| Error::at_token(1, line, range_token, format!("failed to parse layer range {}: {}", range_token, e)))?;
+        if range < 2
+        {
+            return Err(Error::at_token(1, line, range_token, "layer range has to be at least 2"));
+        }
+        Ok(Layer::new(depth, range))
     }
 }
 
+#[derive(Clone)]
 enum PacketState { Moving(u32), Standing}
 
-struct Firewall
+#[derive(Clone)]
+pub struct Firewall
 {
     layers: HashMap<u32, Layer>,
     packet_state: PacketState,
@@ -111,34 +148,26 @@ impl Firewall
         Firewall{layers: HashMap::new(), packet_state: PacketState::Standing}
     }
 
-    fn add_layer(self: &mut Self, line: &str)
+    fn add_layer(self: &mut Self, line: &str) -> Result<(), Error>
     {
-        let layer = Layer::parse(line);
+        let layer = Layer::parse(line)?;
         self.layers.insert(layer.depth, layer);
+        Ok(())
     }
 
-
-    #[cfg(test)]
-    fn from_str(lines: &str) -> Firewall
+    fn from_str(lines: &str) -> Result<Firewall, Error>
     {
         let mut firewall = Firewall::new();
-        for line in lines.split('\n')
+        for (idx, line) in lines.split('\n').enumerate().filter(|&(_, l)| !l.trim().is_empty())
         {
-            firewall.add_layer(line);
+            firewall.add_layer(line).map_err(|e| e.on_line(idx + 1))?;
         }
-        firewall
-    }
 
-    fn from_file(path: &str) -> Firewall
-    {
-        let mut firewall = Firewall::new();
-        let file = File::open(path).expect("Failed to open connections file"); 
-        let reader = BufReader::new(file); 
-        for line in reader.lines().filter_map(|res| res.ok())
+        if firewall.layers.is_empty()
         {
-            firewall.add_layer(&line);
+            return Err(Error::parse(1, 1, "firewall has no layers"));
         }
-        firewall
+        Ok(firewall)
     }
 
     fn move_scanner(self: &mut Self)
@@ -201,7 +230,7 @@ impl Firewall
 
     fn max_depth(self: &Self) -> u32
     {
-        self.layers.keys().cloned().max().unwrap()
+        self.layers.keys().cloned().max().unwrap_or(0)
     }
 
     fn caught_delay(self: &Self, mut delay: u32) -> bool
@@ -217,18 +246,14 @@ impl Firewall
         false
     }
 
-    fn delay(self: &mut Self) -> u32
+    fn delay(self: &Self) -> u32
     {
         let mut delay = 0;
         while self.caught_delay(delay)
         {
             delay+=1;
         }
-        if delay % 1000 == 0
-        {
-            println!("delay: {}", delay)
-        }
-        return delay
+        delay
     }
 }
 
@@ -241,29 +266,14 @@ mod tests
     fn test_parsing()
     {
         let lines = "0: 3\n1: 2\n4: 4\n6: 4";
-        let firewall = Firewall::from_str(lines);
-        assert!( match firewall.packet_state {
-            PacketState::Standing => true,
-            _ => false
-        });
+        let firewall = Firewall::from_str(lines).unwrap();
+        assert!(matches!(firewall.packet_state, PacketState::Standing));
         assert_eq!(firewall.layers.get(&0).unwrap().range, 3);
         assert_eq!(firewall.layers.get(&1).unwrap().range, 2);
-        assert!(match firewall.layers.get(&2)
-                {
-                    None => true,
-                    _ => false
-                });
-        assert!(match firewall.layers.get(&3)
-                {
-                    None => true,
-                    _ => false
-                });
+        assert!(!firewall.layers.contains_key(&2));
+        assert!(!firewall.layers.contains_key(&3));
         assert_eq!(firewall.layers.get(&4).unwrap().range, 4);
-        assert!(match firewall.layers.get(&5)
-                {
-                    None => true,
-                    _ => false
-                });
+        assert!(!firewall.layers.contains_key(&5));
         assert_eq!(firewall.layers.get(&6).unwrap().range, 4);
     }
 
@@ -271,10 +281,10 @@ mod tests
     fn test_severity()
     {
         let lines = "0: 3\n1: 2\n4: 4\n6: 4";
-        let mut firewall = Firewall::from_str(lines);
+        let mut firewall = Firewall::from_str(lines).unwrap();
         assert_eq!(firewall.severity(), 24);
 
-        let mut firewall = Firewall::from_str(lines);
+        let mut firewall = Firewall::from_str(lines).unwrap();
         firewall.layers.get_mut(&0).unwrap().scanner_pos = 2;
         firewall.layers.get_mut(&0).unwrap().scanner_dir = Dir::Down;
         firewall.layers.get_mut(&1).unwrap().scanner_pos = 0;
@@ -290,7 +300,7 @@ mod tests
     fn test_scanner_pos()
     {
         let lines = "0: 3\n1: 2\n4: 4\n6: 4";
-        let firewall = Firewall::from_str(lines);
+        let firewall = Firewall::from_str(lines).unwrap();
         assert_eq!(firewall.layers[&0].scanner_pos(0), 0);
         assert_eq!(firewall.layers[&0].scanner_pos(1), 1);
         assert_eq!(firewall.layers[&0].scanner_pos(2), 2);
@@ -332,17 +342,17 @@ mod tests
     fn caught_delay()
     {
         let lines = "0: 3\n1: 2\n4: 4\n6: 4";
-        let firewall = Firewall::from_str(lines);
-        assert_eq!(firewall.caught_delay(0), true);
-        assert_eq!(firewall.caught_delay(1), true);
-        assert_eq!(firewall.caught_delay(2), true);
-        assert_eq!(firewall.caught_delay(3), true);
-        assert_eq!(firewall.caught_delay(4), true);
-        assert_eq!(firewall.caught_delay(5), true);
-        assert_eq!(firewall.caught_delay(6), true);
-        assert_eq!(firewall.caught_delay(7), true);
-        assert_eq!(firewall.caught_delay(8), true);
-        assert_eq!(firewall.caught_delay(9), true);
-        assert_eq!(firewall.caught_delay(10), false);
+        let firewall = Firewall::from_str(lines).unwrap();
+        assert!(firewall.caught_delay(0));
+        assert!(firewall.caught_delay(1));
+        assert!(firewall.caught_delay(2));
+        assert!(firewall.caught_delay(3));
+        assert!(firewall.caught_delay(4));
+        assert!(firewall.caught_delay(5));
+        assert!(firewall.caught_delay(6));
+        assert!(firewall.caught_delay(7));
+        assert!(firewall.caught_delay(8));
+        assert!(firewall.caught_delay(9));
+        assert!(!firewall.caught_delay(10));
     }
 }
\ No newline at end of file
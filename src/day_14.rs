@@ -1,17 +1,46 @@
 use day_10::knot_hash;
 use std::ops::Index;
 use std::ops::IndexMut;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let disk = Grid::from_key("oundnydw");
-    format!("{}", disk.count_used())
-}
+pub struct Day14;
 
-pub fn second_puzzle() -> String
+impl Solution for Day14
 {
-    let disk = Grid::from_key("oundnydw");
-    format!("{}", disk.count_regions())
+    type Input = String;
+
+    fn day(&self) -> u32
+    {
+        14
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Disk Defragmentation"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "disk.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<String, Error>
+    {
+        Ok(String::from(input.trim()))
+    }
+
+    fn first_puzzle(&self, key: &String) -> Result<String, Error>
+    {
+        let disk = Grid::from_key(key);
+        Ok(format!("{}", disk.count_used()))
+    }
+
+    fn second_puzzle(&self, key: &String) -> Result<String, Error>
+    {
+        let disk = Grid::from_key(key);
+        Ok(format!("{}", disk.count_regions()))
+    }
 }
 
 fn row_key(key: &str, row: u32) -> String
@@ -21,27 +50,13 @@ fn row_key(key: &str, row: u32) -> String
 
 fn row(key: &str, row_num: u32) -> Vec<u8>
 {
-    let mut bytes = Vec::new();
     let row_key = row_key(key, row_num);
     let row_hash = knot_hash(&row_key);
-    let mut iter =  row_hash.as_str().chars();
-    while let Some(upper_nibble) = iter.next()
-    {
-        if let Some(lower_nibble) = iter.next()
-        {
-           let byte = u8::from_str_radix(&format!("{}{}",upper_nibble, lower_nibble), 16)
-                         .expect(&format!("Invalid characters: {}{}", upper_nibble, lower_nibble));
-           bytes.push(byte);
-        }
-        else
-        {
-            panic!("Odd number of nibbles!");
-        }
-    }
-    bytes
+    (0 .. row_hash.len() / 2).filter_map(|idx| u8::from_str_radix(&row_hash[2 * idx .. 2 * idx + 2], 16).ok())
+                             .collect()
 }
 
-fn format_row(bytes: &Vec<u8>) -> String
+fn format_row(bytes: &[u8]) -> String
 {
     let mut row = String::new();
     for byte in bytes.iter()
@@ -69,12 +84,7 @@ impl Grid {
         for i in 0..128 {
             let row_str = format_row(&row(key, i));
             for (j, ch) in row_str.chars().enumerate() {
-                if ch == '1' {
-                    disk.grid[i as usize][j as usize] = true;
-                }
-                else {
-                    disk.grid[i as usize][j as usize] = false;
-                }
+                disk.grid[i as usize][j] = ch == '1';
             }
         } 
         disk
@@ -124,7 +134,7 @@ impl Grid {
             }
         };
 
-        if let Some(_) = row.checked_sub(1) {
+        if row.checked_sub(1).is_some() {
             proceed((row -1, col));
         }
 
@@ -132,7 +142,7 @@ impl Grid {
             proceed((row + 1, col));
         }
         
-        if let Some(_) = col.checked_sub(1) {
+        if col.checked_sub(1).is_some() {
             proceed((row, col - 1));
         }
 
@@ -1,12 +1,43 @@
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let judge = Judge::new(Generator::a(679), Generator::b(771));
-    format!("{}", judge.judge(40000000))
-}
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input = (u64, u64);
+
+    fn day(&self) -> u32 {
+        15
+    }
 
-pub fn second_puzzle() -> String {
-    let judge = Judge::new(Generator::a(679), Generator::b(771));
-    format!("{}", judge.judge_cond(5000000))
+    fn name(&self) -> &'static str {
+        "Dueling Generators"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "generators.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<(u64, u64), Error> {
+        let mut start_vals = input.lines()
+                                  .enumerate()
+                                  .filter_map(|(idx, line)| line.split_whitespace().last().map(|token| (idx, line, token)))
+                                  .map(|(idx, line, token)| token.parse::<u64>()
+                                                                 .map_err(|e| Error::at_token(idx + 1, line, token, format!("failed to parse start value {}: {}", token, e))));
+        let a = start_vals.next().unwrap_or_else(|| Err(Error::parse(1, 1, "missing generator A")))?;
+        let b = start_vals.next().unwrap_or_else(|| Err(Error::parse(2, 1, "missing generator B")))?;
+        Ok((a, b))
+    }
+
+    fn first_puzzle(&self, &(a, b): &(u64, u64)) -> Result<String, Error> {
+        let judge = Judge::new(Generator::a(a), Generator::b(b));
+        Ok(format!("{}", judge.judge(40000000)))
+    }
+
+    fn second_puzzle(&self, &(a, b): &(u64, u64)) -> Result<String, Error> {
+        let judge = Judge::new(Generator::a(a), Generator::b(b));
+        Ok(format!("{}", judge.judge_cond(5000000)))
+    }
 }
 
 
@@ -40,7 +71,7 @@ impl Generator {
 
     fn generate_cond(self: &mut Self) -> Option<u64> {
         self.generate();
-        if self.previous % self.mod_cond == 0 {
+        if self.previous.is_multiple_of(self.mod_cond) {
             Some(self.previous)
         }
         else {
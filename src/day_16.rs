@@ -1,55 +1,73 @@
-use std::io::prelude::*; 
-use std::io::BufReader; 
-use std::fs::File; 
 use std::collections::HashSet;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let mut hall = DanceHall::new();
-    let moves = DanceHall::read_moves("dance.txt");
-    hall.performe_dance(&moves);
-    hall.to_string()
-}
+pub struct Day16;
 
-pub fn second_puzzle() -> String {
-    let mut hall = DanceHall::new();
-    let moves = DanceHall::read_moves("dance.txt");
-    let miliard = 1000000000;
-    if let Some(cycle) = DanceHall::find_cycle(&moves, miliard) {
-        let remainder = miliard % cycle;
-        println!("cycle: {}\tremainder: {}",cycle, remainder);
-        for _ in 0 .. remainder {
-            hall.performe_dance(&moves);
-        }
+impl Solution for Day16 {
+    type Input = Vec<DanceMove>;
+
+    fn day(&self) -> u32 {
+        16
+    }
+
+    fn name(&self) -> &'static str {
+        "Permutation Promenade"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "dance.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<DanceMove>, Error> {
+        DanceHall::read_moves(input)
+    }
+
+    fn first_puzzle(&self, moves: &Vec<DanceMove>) -> Result<String, Error> {
+        let mut hall = DanceHall::new();
+        hall.performe_dance(moves);
+        Ok(hall.to_string())
     }
-    else {
-        panic!("No cycle found!");
+
+    fn second_puzzle(&self, moves: &Vec<DanceMove>) -> Result<String, Error> {
+        let mut hall = DanceHall::new();
+        let miliard = 1000000000;
+        if let Some(cycle) = DanceHall::find_cycle(moves, miliard) {
+            let remainder = miliard % cycle;
+            for _ in 0 .. remainder {
+                hall.performe_dance(moves);
+            }
+            Ok(hall.to_string())
+        }
+        else {
+            Err(Error::no_solution("no cycle found"))
+        }
     }
-    hall.to_string()
 }
 
 #[derive(Clone)]
 #[derive(PartialEq)]
-enum Program {A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P}
+pub enum Program {A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P}
 
-fn char_to_prog(c: char) -> Program {
+fn char_to_prog(c: char) -> Option<Program> {
     match c {
-        'a' => Program::A,
-        'b' => Program::B,
-        'c' => Program::C,
-        'd' => Program::D,
-        'e' => Program::E,
-        'f' => Program::F,
-        'g' => Program::G,
-        'h' => Program::H,
-        'i' => Program::I,
-        'j' => Program::J,
-        'k' => Program::K,
-        'l' => Program::L,
-        'm' => Program::M,
-        'n' => Program::N,
-        'o' => Program::O,
-        'p' => Program::P,
-        _   => panic!("Invalid char: {}", c) 
+        'a' => Some(Program::A),
+        'b' => Some(Program::B),
+        'c' => Some(Program::C),
+        'd' => Some(Program::D),
+        'e' => Some(Program::E),
+        'f' => Some(Program::F),
+        'g' => Some(Program::G),
+        'h' => Some(Program::H),
+        'i' => Some(Program::I),
+        'j' => Some(Program::J),
+        'k' => Some(Program::K),
+        'l' => Some(Program::L),
+        'm' => Some(Program::M),
+        'n' => Some(Program::N),
+        'o' => Some(Program::O),
+        'p' => Some(Program::P),
+        _   => None
     }
 }
 fn prog_to_char(prog: &Program) -> char {
@@ -73,7 +91,7 @@ fn prog_to_char(prog: &Program) -> char {
     }
 }
 
-enum DanceMove {Spin(usize), Exchange(usize, usize), Partner(Program, Program) }
+pub enum DanceMove {Spin(usize), Exchange(usize, usize), Partner(Program, Program) }
 
 struct DanceHall {
     dancers: Vec<Program>
@@ -88,57 +106,50 @@ impl DanceHall {
         DanceHall{dancers}
     }
 
-    fn parse(token: &str) -> DanceMove {
+    fn parse(token: &str) -> Result<DanceMove, Error> {
+        let position = |column: usize, name: &str, limit: usize| {
+            let arg = token.get(column ..).unwrap_or("");
+            let arg = &arg[.. arg.find('/').unwrap_or(arg.len())];
+            arg.parse::<usize>()
+               .ok()
+               .filter(|&pos| pos < limit)
+               .ok_or_else(|| Error::parse(1, column + 1, format!("failed to parse {}: {}", name, token)))
+        };
+        let program = |column: usize, name: &str| {
+            token.chars()
+                 .nth(column)
+                 .and_then(char_to_prog)
+                 .ok_or_else(|| Error::parse(1, column + 1, format!("invalid {}: {}", name, token)))
+        };
+
         match token.chars().nth(0) {
             Some('s') => {
-                let count = token.chars()
-                                 .skip(1)
-                                 .collect::<String>()
-                                 .parse::<usize>()
-                                 .expect(&format!("Failed to parse count: {}",token));
-
-                return DanceMove::Spin(count as usize)
+                let count = position(1, "count", 17)?;
+                Ok(DanceMove::Spin(count))
             },
             Some('x') => {
-                let first_idx = token.chars()
-                                     .skip(1)
-                                     .take_while(|c| *c != '/')
-                                     .collect::<String>()
-                                     .parse::<usize>()
-                                     .expect(&format!("Failed to parse first_idx: {}", token));
-
-                let second_idx = token.chars()
-                                      .skip_while(|c| *c != '/')
-                                      .skip(1)
-                                      .collect::<String>()
-                                      .parse::<usize>()
-                                      .expect(&format!("Failed to parse second_idx: {}", token));
-
-                return DanceMove::Exchange(first_idx as usize, second_idx as usize)
+                let first_idx = position(1, "first_idx", 16)?;
+                let second_idx = position(token.find('/').map_or(token.len(), |idx| idx + 1), "second_idx", 16)?;
+                Ok(DanceMove::Exchange(first_idx, second_idx))
             },
             Some('p') => {
-                let first_prog = char_to_prog(token.chars()
-                                                   .nth(1)
-                                                   .expect(&format!("Missing first_prog: {}", token)));
-                let second_prog = char_to_prog(token.chars()
-                                                    .nth(3)
-                                                    .expect(&format!("Missing second_prog: {}", token)));
-
-                return DanceMove::Partner(first_prog, second_prog)
+                let first_prog = program(1, "first_prog")?;
+                let second_prog = program(3, "second_prog")?;
+                Ok(DanceMove::Partner(first_prog, second_prog))
             },
-            _ => panic!("Invalid token: {}", token)
+            _ => Err(Error::parse(1, 1, format!("invalid token: {}", token)))
         }
     }
 
     fn perform_move(self: &mut Self, dance_move: &DanceMove) {
-        match dance_move {
-            &DanceMove::Spin(count) => self.spin(count),
-            &DanceMove::Exchange(first_idx, second_idx) => self.exchange(first_idx, second_idx),
-            &DanceMove::Partner(ref first_prog, ref second_prog) => self.partner(first_prog, second_prog)
+        match *dance_move {
+            DanceMove::Spin(count) => self.spin(count),
+            DanceMove::Exchange(first_idx, second_idx) => self.exchange(first_idx, second_idx),
+            DanceMove::Partner(ref first_prog, ref second_prog) => self.partner(first_prog, second_prog)
         }
     }
 
-    fn performe_dance(self: &mut Self, dance: &Vec<DanceMove>) {
+    fn performe_dance(self: &mut Self, dance: &[DanceMove]) {
         for m in dance.iter() {
             self.perform_move(m);
         }
@@ -149,9 +160,9 @@ impl DanceHall {
         let skip = len - count;
         self.dancers = self.dancers.iter()
                                    .cycle()
-                                   .cloned()
                                    .skip(skip)
                                    .take(len)
+                                   .cloned()
                                    .collect();
     }
 
@@ -161,13 +172,12 @@ impl DanceHall {
 
     fn partner(self: &mut Self, first_prog: &Program, second_prog: &Program) {
         let first_idx = self.dancers.iter()
-                                    .position(|prog| prog == first_prog)
-                                    .expect(&format!("Failed to find first_prog: {}", prog_to_char(&first_prog)));
-
+                                    .position(|prog| prog == first_prog);
         let second_idx = self.dancers.iter()
-                                     .position(|prog| prog == second_prog)
-                                     .expect(&format!("Failed to find second_prog: {}", prog_to_char(&second_prog)));
-        self.dancers.swap(first_idx, second_idx);
+                                     .position(|prog| prog == second_prog);
+        if let (Some(first_idx), Some(second_idx)) = (first_idx, second_idx) {
+            self.dancers.swap(first_idx, second_idx);
+        }
     }
 
     fn to_string(self: &Self) -> String {
@@ -178,19 +188,17 @@ impl DanceHall {
        hall 
     }
 
-    fn read_moves(path: &str) -> Vec<DanceMove> {
+    fn read_moves(input: &str) -> Result<Vec<DanceMove>, Error> {
         let mut moves = Vec::new();
-        let file = File::open(path).expect("Failed to open dance file"); 
-        let reader = BufReader::new(file); 
-        for line in reader.lines().filter_map(|res| res.ok()) {
-            for token in line.split(',').map(|t| t.trim()) {
-                moves.push(DanceHall::parse(token));
+        for (idx, line) in input.lines().enumerate() {
+            for token in line.split(',').map(|t| t.trim()).filter(|t| !t.is_empty()) {
+                moves.push(DanceHall::parse(token).map_err(|e| e.within(line, token).on_line(idx + 1))?);
             }
         }
-        moves
+        Ok(moves)
     }
 
-    fn find_cycle(dance: &Vec<DanceMove>, max_repeat: usize) -> Option<usize> {
+    fn find_cycle(dance: &[DanceMove], max_repeat: usize) -> Option<usize> {
         let mut hall = DanceHall::new();
         let mut states = HashSet::new();
         for i in 0 .. max_repeat {
@@ -213,30 +221,17 @@ mod tests {
 
     #[test]
     fn test_parse() {
-        assert!(match DanceHall::parse("s1") {
-            DanceMove::Spin(1) => true,
-            _ => false
-        });
-        assert!(match DanceHall::parse("x3/4") {
-            DanceMove::Exchange(3,4) => true,
-            _ => false
-        });
-        assert!(match DanceHall::parse("pe/b") {
-            DanceMove::Partner(Program::E, Program::B) => true,
-            _ => false
-        });
+        assert!(matches!(DanceHall::parse("s1").unwrap(), DanceMove::Spin(1)));
+        assert!(matches!(DanceHall::parse("x3/4").unwrap(), DanceMove::Exchange(3,4)));
+        assert!(matches!(DanceHall::parse("pe/b").unwrap(), DanceMove::Partner(Program::E, Program::B)));
     }
 
     #[test]
     fn test_dance() {
         let mut hall = DanceHall::new();
-        println!("init: {}", hall.to_string());
-        hall.perform_move(&DanceHall::parse("s1"));
-        println!("spin: {}", hall.to_string());
-        hall.perform_move(&DanceHall::parse("x3/4"));
-        println!("exchane: {}", hall.to_string());
-        hall.perform_move(&DanceHall::parse("pe/b"));
-        println!("partner: {}", hall.to_string());
+        hall.perform_move(&DanceHall::parse("s1").unwrap());
+        hall.perform_move(&DanceHall::parse("x3/4").unwrap());
+        hall.perform_move(&DanceHall::parse("pe/b").unwrap());
         assert_eq!(&hall.to_string(), "paedcbfghijklmno");
     }
 }
\ No newline at end of file
@@ -1,25 +1,49 @@
 use std::fmt;
 use std::collections::HashMap;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
+pub struct Day17;
 
-    let mut lock = SpinLock::new(324);
-    for _ in 0 .. 2017 {
-        lock.spin();
+impl Solution for Day17 {
+    type Input = usize;
+
+    fn day(&self) -> u32 {
+        17
     }
-    format!("{}", lock.buffer[lock.pos + 1])
-}
 
-pub fn second_puzzle() -> String {
-    let fifty_milions = 50000000;
-    let mut lock = SpinLockSim::new(324, fifty_milions + 1);
-    for t in (0..(fifty_milions+1)).rev() {
-            if lock.pos(t) == 1 {
-                return format!("{}", SpinLockSim::val(t));
-            }
+    fn name(&self) -> &'static str {
+        "Spinlock"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "spinlock.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<usize, Error> {
+        let token = input.trim();
+        token.parse().map_err(|e| Error::at_token(1, input, token, format!("invalid step {}: {}", token, e)))
+    }
+
+    fn first_puzzle(&self, &step: &usize) -> Result<String, Error> {
+        let mut lock = SpinLock::new(step);
+        for _ in 0 .. 2017 {
+            lock.spin();
+        }
+        Ok(format!("{}", lock.buffer[(lock.pos + 1) % lock.buffer.len()]))
     }
 
-    panic!("Pos 1 never hit!");
+    fn second_puzzle(&self, &step: &usize) -> Result<String, Error> {
+        let fifty_milions = 50000000;
+        let lock = SpinLockSim::new(step, fifty_milions + 1);
+        for t in (0..(fifty_milions+1)).rev() {
+                if lock.pos(t) == Some(1) {
+                    return Ok(format!("{}", SpinLockSim::val(t)));
+                }
+        }
+
+        Err(Error::no_solution("pos 1 never hit"))
+    }
 }
 
 struct SpinLock {
@@ -32,7 +56,7 @@ impl SpinLock {
     fn new(step: usize) -> SpinLock {
         let mut buffer = Vec::with_capacity(2018);
         buffer.push(0);
-        SpinLock { step, buffer: buffer, pos: 0 }
+        SpinLock { step, buffer, pos: 0 }
     }
 
 
@@ -47,21 +71,13 @@ impl SpinLock {
 
     fn spin(&mut self) {
         let val = self.nex_val();
+        // `next_pos` is at most the buffer length, where `insert` appends.
         self.pos = self.next_pos();
-        if self.pos < self.buffer.len() {
-            self.buffer.insert(self.pos, val);
-        }
-        else if self.pos == self.buffer.len() {
-            self.buffer.push(val);
-        }
-        else {
-            panic!("Invalid pos: {:?}", self);
-        }
+        self.buffer.insert(self.pos, val);
     }
 }
 
 struct SpinLockSim {
-    cache_size: usize,
     cache: HashMap<usize, usize>
 }
 
@@ -73,20 +89,16 @@ impl SpinLockSim {
             let pos = (cache[&(t-1)] + step) % SpinLockSim::buffer_len(t-1) + 1;
             cache.insert(t, pos);
         }
-        SpinLockSim {cache_size, cache}
+        SpinLockSim {cache}
     }
 
     fn buffer_len(time: usize) -> usize {
         1 + time
     }
 
-    fn pos(&mut self, time: usize) -> usize {
-        if time < self.cache_size {
-            self.cache[&time]
-        }
-        else {
-            panic!("Not implemented!");
-        }
+    /// Position of the value inserted at `time`, `None` past the simulated range.
+    fn pos(&self, time: usize) -> Option<usize> {
+        self.cache.get(&time).cloned()
     }
 
     fn val(time: usize) -> usize {
@@ -0,0 +1,197 @@
+use std::fmt;
+use super::instruction::Instruction;
+
+/// What `add`, `sub`, `mul` and `mod` do with results outside of `i64`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Overflow {
+    /// Stops the program with a `Fault`.
+    #[default]
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+/// Sign of the result of `mod` with a negative operand.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Modulo {
+    /// Rust's `%`, the sign of the dividend.
+    #[default]
+    Remainder,
+    /// Always in `0 .. |divisor|`.
+    Euclidean,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Arithmetic {
+    pub overflow: Overflow,
+    pub modulo: Modulo,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FaultKind {
+    Overflow,
+    ModByZero,
+}
+
+/// Arithmetic error stopping a program at instruction `ic`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Fault {
+    pub ic: i64,
+    pub instruction: Instruction,
+    pub kind: FaultKind,
+}
+
+impl Overflow {
+    pub fn parse(name: &str) -> Option<Overflow> {
+        match name {
+            "checked" => Some(Overflow::Checked),
+            "wrapping" => Some(Overflow::Wrapping),
+            "saturating" => Some(Overflow::Saturating),
+            _ => None,
+        }
+    }
+}
+
+impl Modulo {
+    pub fn parse(name: &str) -> Option<Modulo> {
+        match name {
+            "remainder" => Some(Modulo::Remainder),
+            "euclidean" => Some(Modulo::Euclidean),
+            _ => None,
+        }
+    }
+}
+
+impl Arithmetic {
+    pub fn add(&self, lhs: i64, rhs: i64) -> Result<i64, FaultKind> {
+        match self.overflow {
+            Overflow::Checked => lhs.checked_add(rhs).ok_or(FaultKind::Overflow),
+            Overflow::Wrapping => Ok(lhs.wrapping_add(rhs)),
+            Overflow::Saturating => Ok(lhs.saturating_add(rhs)),
+        }
+    }
+
+    pub fn sub(&self, lhs: i64, rhs: i64) -> Result<i64, FaultKind> {
+        match self.overflow {
+            Overflow::Checked => lhs.checked_sub(rhs).ok_or(FaultKind::Overflow),
+            Overflow::Wrapping => Ok(lhs.wrapping_sub(rhs)),
+            Overflow::Saturating => Ok(lhs.saturating_sub(rhs)),
+        }
+    }
+
+    pub fn mul(&self, lhs: i64, rhs: i64) -> Result<i64, FaultKind> {
+        match self.overflow {
+            Overflow::Checked => lhs.checked_mul(rhs).ok_or(FaultKind::Overflow),
+            Overflow::Wrapping => Ok(lhs.wrapping_mul(rhs)),
+            Overflow::Saturating => Ok(lhs.saturating_mul(rhs)),
+        }
+    }
+
+    /// Faults on a zero `rhs` whatever the overflow policy, `i64::MIN mod -1` only overflows when checked.
+    pub fn modulo(&self, lhs: i64, rhs: i64) -> Result<i64, FaultKind> {
+        if rhs == 0 {
+            return Err(FaultKind::ModByZero);
+        }
+        match (self.overflow, self.modulo) {
+            (Overflow::Checked, Modulo::Remainder) => lhs.checked_rem(rhs).ok_or(FaultKind::Overflow),
+            (Overflow::Checked, Modulo::Euclidean) => lhs.checked_rem_euclid(rhs).ok_or(FaultKind::Overflow),
+            (_, Modulo::Remainder) => Ok(lhs.wrapping_rem(rhs)),
+            (_, Modulo::Euclidean) => Ok(lhs.wrapping_rem_euclid(rhs)),
+        }
+    }
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Overflow::Checked => "checked",
+            Overflow::Wrapping => "wrapping",
+            Overflow::Saturating => "saturating",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Modulo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Modulo::Remainder => "remainder",
+            Modulo::Euclidean => "euclidean",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            FaultKind::Overflow => "arithmetic overflow",
+            FaultKind::ModByZero => "mod by zero",
+        };
+        write!(f, "ic {}: {}: {}", self.ic, kind, self.instruction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error::Error;
+    use super::super::{Assembler, Dialect, assemble};
+
+    #[test]
+    fn test_arithmetic() {
+        let checked = Arithmetic::default();
+        let wrapping = Arithmetic { overflow: Overflow::Wrapping, modulo: Modulo::Euclidean };
+        let saturating = Arithmetic { overflow: Overflow::Saturating, modulo: Modulo::Remainder };
+        assert_eq!(checked.add(i64::MAX, 1), Err(FaultKind::Overflow));
+        assert_eq!(wrapping.add(i64::MAX, 1), Ok(i64::MIN));
+        assert_eq!(saturating.mul(i64::MIN, 2), Ok(i64::MIN));
+        assert_eq!(saturating.sub(i64::MIN, 1), Ok(i64::MIN));
+        assert_eq!(checked.modulo(-7, 3), Ok(-1));
+        assert_eq!(wrapping.modulo(-7, 3), Ok(2));
+        assert_eq!(checked.modulo(i64::MIN, -1), Err(FaultKind::Overflow));
+        assert_eq!(saturating.modulo(i64::MIN, -1), Ok(0));
+        assert_eq!(wrapping.modulo(5, 0), Err(FaultKind::ModByZero));
+
+        let program = assemble("set a 2\nmul a a\njgz a -1").unwrap();
+        let mut assembler = Assembler::with_program(0, Dialect::Duet, &program).unwrap();
+        match assembler.run(u64::MAX) {
+            Err(Error::Fault(fault)) => {
+                assert_eq!((fault.ic, fault.instruction, fault.kind), (1, program[1], FaultKind::Overflow));
+                assert_eq!(fault.to_string(), "ic 1: arithmetic overflow: mul a a");
+            },
+            other => panic!("expected an overflow, got {:?}", other),
+        }
+
+        let mut assembler = Assembler::with_program(0, Dialect::Duet, &program).unwrap();
+        assembler.set_arithmetic(wrapping);
+        assert_eq!(assembler.run(u64::MAX).unwrap(), 13);
+        assert_eq!(assembler.register('a'), 0);
+
+        let program = assemble("set a 1\njgz a 9223372036854775807").unwrap();
+        let mut assembler = Assembler::with_program(0, Dialect::Duet, &program).unwrap();
+        assembler.set_arithmetic(wrapping);
+        assert_eq!(assembler.run(u64::MAX).unwrap_err().to_string(), "fault: ic 1: arithmetic overflow: jgz a 9223372036854775807");
+    }
+
+    #[test]
+    fn test_fused_overflow() {
+        let program = assemble("add a 1\nadd c -1\njgz c -2").unwrap();
+        for &(overflow, expected) in &[(Overflow::Wrapping, i64::MIN + 1), (Overflow::Saturating, i64::MAX)] {
+            let mut assembler = Assembler::with_program(0, Dialect::Duet, &program).unwrap();
+            assembler.set_arithmetic(Arithmetic { overflow, modulo: Modulo::Remainder });
+            assembler.registers[0] = i64::MAX - 1;
+            assembler.registers[2] = 3;
+            assembler.optimize();
+            assembler.run(u64::MAX).unwrap();
+            assert_eq!(assembler.register('a'), expected);
+        }
+
+        let mut assembler = Assembler::with_program(0, Dialect::Duet, &program).unwrap();
+        assembler.registers[0] = i64::MAX - 1;
+        assembler.registers[2] = 3;
+        assembler.optimize();
+        assert_eq!(assembler.run(u64::MAX).unwrap_err().to_string(), "fault: ic 0: arithmetic overflow: add a 1");
+        assert_eq!(assembler.register('c'), 2);
+    }
+}
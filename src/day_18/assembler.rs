@@ -0,0 +1,501 @@
+use std::io::prelude::*;
+use std::fs::File;
+use std::collections::VecDeque;
+use error::Error;
+use super::arithmetic::{Arithmetic, Fault, FaultKind, Overflow};
+use super::instruction::*;
+use super::diagnostic::{Diagnostics, assemble_dialect, source_lines};
+use super::dialect::Dialect;
+use super::optimizer::{self, Fused};
+use super::profile::Profile;
+use super::registers::Registers;
+use super::snapshot::Snapshot;
+use super::trace::{End, Trace};
+
+#[derive(Clone)]
+pub struct Assembler {
+    pub registers: Registers,
+    pub instructions: Vec<Instruction>,
+    /// Source line of every instruction, counted from 1.
+    lines: Vec<usize>,
+    dialect: Dialect,
+    arithmetic: Arithmetic,
+    messages: VecDeque<i64>,
+    last_sound: Option<i64>,
+    profile: Option<Profile>,
+    fused: Vec<Option<Fused>>,
+    trace: Option<Trace>,
+    pub ic: i64
+}
+
+impl Assembler {
+    /// Program `id`, which starts with `p = id` and every other register at 0.
+    pub fn new(id: i64, dialect: Dialect) -> Assembler {
+        let mut registers = Registers::new();
+        registers[register('p').unwrap_or(0)] = id;
+        Assembler {
+            registers,
+            instructions: Vec::new(),
+            lines: Vec::new(),
+            dialect,
+            arithmetic: Arithmetic::default(),
+            messages: VecDeque::new(),
+            last_sound: None,
+            profile: None,
+            fused: Vec::new(),
+            trace: None,
+            ic: 0 }
+    }
+
+    /// Errors on the first instruction `dialect` does not support.
+    pub fn with_program(id: i64, dialect: Dialect, program: &[Instruction]) -> Result<Assembler, Error> {
+        if let Some(ic) = program.iter().position(|instruction| !dialect.supports(instruction)) {
+            return Err(Error::invalid_state(format!("ic {}: {} is not part of the {} dialect", ic, program[ic], dialect)));
+        }
+        let mut assembler = Assembler::new(id, dialect);
+        assembler.instructions.extend_from_slice(program);
+        assembler.lines.extend(1 ..= program.len());
+        Ok(assembler)
+    }
+
+    /// `with_program` starting from the `initial` registers instead of only `p = id`.
+    pub fn with_initial(initial: Registers, dialect: Dialect, program: &[Instruction]) -> Result<Assembler, Error> {
+        let mut assembler = Assembler::with_program(0, dialect, program)?;
+        assembler.registers = initial;
+        Ok(assembler)
+    }
+
+    /// Resumes the machine saved in `snapshot`, erroring like `with_program` on instructions outside its dialect.
+    pub fn from_snapshot(snapshot: Snapshot) -> Result<Assembler, Error> {
+        let mut assembler = Assembler::with_initial(snapshot.registers, snapshot.dialect, &snapshot.instructions)?;
+        assembler.arithmetic = snapshot.arithmetic;
+        assembler.messages = snapshot.messages;
+        assembler.last_sound = snapshot.last_sound;
+        assembler.ic = snapshot.ic;
+        assembler.lines = snapshot.lines;
+        Ok(assembler)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            dialect: self.dialect,
+            arithmetic: self.arithmetic,
+            registers: self.registers.clone(),
+            instructions: self.instructions.clone(),
+            lines: (0 .. self.instructions.len()).filter_map(|ic| self.line(ic as i64)).collect(),
+            messages: self.messages.clone(),
+            last_sound: self.last_sound,
+            ic: self.ic,
+        }
+    }
+
+    /// Numbers the instructions by the source lines they were assembled from, see `source_lines`.
+    pub fn set_lines(&mut self, lines: &[usize]) {
+        self.lines = lines.to_vec();
+    }
+
+    /// Source line of instruction `ic`, `None` outside the program.
+    pub fn line(&self, ic: i64) -> Option<usize> {
+        if ic < 0 || ic >= self.instructions.len() as i64 {
+            return None;
+        }
+        let ic = ic as usize;
+        Some(self.lines.get(ic).cloned().unwrap_or(ic + 1))
+    }
+
+    /// Index of the instruction on source line `line`, if there is one.
+    pub fn instruction_on_line(&self, line: usize) -> Option<usize> {
+        (0 .. self.instructions.len()).find(|&ic| self.line(ic as i64) == Some(line))
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    /// Checked arithmetic with Rust's remainder unless set otherwise.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    /// Value of the register called `name`, 0 for names that are not registers.
+    pub fn register(&self, name: char) -> i64 {
+        register(name).map_or(0, |reg| self.registers[reg])
+    }
+
+    #[allow(dead_code)]
+    pub fn dump_regs(&self) -> String {
+        let mut s = String::new();
+        for (reg, value) in self.registers.iter() {
+            s.push_str(&format!("{}:{} |", register_name(reg), value));
+        }
+        s
+    }
+
+    /// Starts counting executed instructions and taken jumps.
+    pub fn enable_profile(&mut self) {
+        self.profile = Some(Profile::new());
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Starts recording every executed instruction with the registers it changed.
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(Trace::new(self.registers.clone()));
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Trace recorded so far, recording stops.
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    /// Replaces the loops of the loaded program matching a known idiom by fused instructions.
+    pub fn optimize(&mut self) {
+        self.fused = optimizer::optimize(&self.instructions);
+    }
+
+    /// `base` plus the product of `factors`, `None` if adding the same amount step by step could overflow.
+    /// Every partial sum lies between `base` and the result, so checking the result is enough.
+    fn accumulated(&self, base: i64, factors: &[i64]) -> Option<i64> {
+        if self.arithmetic.overflow == Overflow::Wrapping {
+            return Some(base.wrapping_add(factors.iter().fold(1i64, |product, &factor| product.wrapping_mul(factor))));
+        }
+        base.checked_add(factors.iter().try_fold(1i64, |product, &factor| product.checked_mul(factor))?)
+    }
+
+    /// Runs `fused` if its guard holds, leaving the registers as the loop it replaces would.
+    /// Loops that could overflow are left to the original instructions, which apply the arithmetic policy.
+    fn execute_fused(&mut self, fused: Fused) -> bool {
+        match fused {
+            Fused::Increment { target, step, sign, counter, end } => {
+                let n = self.registers[counter];
+                if n < 1 {
+                    return false;
+                }
+                match self.accumulated(self.registers[target], &[sign, self.value(step), n]) {
+                    Some(value) => self.registers[target] = value,
+                    None => return false,
+                }
+                self.registers[counter] = 0;
+                self.ic = end as i64 + 1;
+            },
+            Fused::Multiply { target, step, sign, source, counter, outer, end } => {
+                let (n, m) = (self.value(source), self.registers[outer]);
+                if n < 1 || m < 1 {
+                    return false;
+                }
+                match self.accumulated(self.registers[target], &[sign, self.value(step), n, m]) {
+                    Some(value) => self.registers[target] = value,
+                    None => return false,
+                }
+                self.registers[counter] = 0;
+                self.registers[outer] = 0;
+                self.ic = end as i64 + 1;
+            },
+            Fused::Divisible { flag, factor, index, value, scratch, end } => {
+                let (d, e, b) = (self.registers[factor], self.registers[index], self.registers[value]);
+                if e >= b {
+                    return false;
+                }
+                // d * e - b and e + 1 - b are linear in e, so their extremes are at the first and last index.
+                let fits = |e: i64| d.checked_mul(e).and_then(|product| product.checked_sub(b)).is_some();
+                if !fits(e) || !fits(b - 1) || (e + 1).checked_sub(b).is_none() {
+                    return false;
+                }
+                let found = if d == 0 { b == 0 } else { b % d == 0 && b / d >= e && b / d < b };
+                if found {
+                    self.registers[flag] = 0;
+                }
+                self.registers[index] = b;
+                self.registers[scratch] = 0;
+                self.ic = end as i64 + 1;
+            },
+        }
+        true
+    }
+
+    pub fn load_program(&mut self, path: &str) -> Result<(), Error> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        Ok(self.load_program_str(&text)?)
+    }
+
+    /// Appends `prog` to the program, nothing is loaded if any line is invalid or outside the dialect.
+    pub fn load_program_str(&mut self, prog: &str) -> Result<(), Diagnostics> {
+        self.instructions.extend(assemble_dialect(prog, self.dialect)?);
+        self.lines.extend(source_lines(prog));
+        Ok(())
+    }
+
+    pub fn terminated(&self) -> bool {
+        self.ic < 0 || self.ic >= self.instructions.len() as i64
+    }
+
+    /// Instruction at `ic`, `None` once the program terminated.
+    pub fn current(&self) -> Option<Instruction> {
+        if self.terminated() {
+            None
+        }
+        else {
+            Some(self.instructions[self.ic as usize])
+        }
+    }
+
+    pub fn push_message(&mut self, value: i64) {
+        self.messages.push_back(value);
+    }
+
+    /// Received messages not consumed by a `rcv` yet.
+    pub fn messages(&self) -> &VecDeque<i64> {
+        &self.messages
+    }
+
+    /// Only Duet programs wait, on a `rcv` with no message queued.
+    pub fn waiting_message(&self) -> bool {
+        match self.current() {
+            Some(Instruction::Rcv(_)) => self.dialect == Dialect::Duet && self.messages.is_empty(),
+            _ => false
+        }
+    }
+
+    fn fault(&self, instruction: Instruction, kind: FaultKind) -> Error {
+        Error::Fault(Fault { ic: self.ic, instruction, kind })
+    }
+
+    fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Reg(reg) => self.registers[reg],
+            Operand::Const(c) => c
+        }
+    }
+
+    /// Faults like the arithmetic instructions when the target does not fit an `i64`.
+    fn jump(&mut self, instruction: Instruction, condition: bool, offset: Operand) -> Result<(), Error> {
+        if condition {
+            let from = self.ic;
+            self.ic = self.ic.checked_add(self.value(offset)).ok_or_else(|| self.fault(instruction, FaultKind::Overflow))?;
+            if let Some(ref mut profile) = self.profile {
+                profile.record_jump(from as usize, self.ic);
+            }
+        }
+        else {
+            self.ic += 1;
+        }
+        Ok(())
+    }
+
+    /// Returns the value sent by a Duet `snd`, or recovered by a Sound `rcv`.
+    pub fn execute_next_instruction(&mut self) -> Result<Option<i64>, Error> {
+        let instruction = match self.current() {
+            Some(instruction) => instruction,
+            None => return Err(Error::invalid_state(format!("ic {}: program terminated", self.ic)))
+        };
+        if !self.dialect.supports(&instruction) {
+            return Err(Error::invalid_state(format!("ic {}: {} is not part of the {} dialect", self.ic, instruction, self.dialect)));
+        }
+        let waiting = self.waiting_message();
+        if !waiting {
+            if let Some(ref mut profile) = self.profile {
+                profile.record(self.ic as usize);
+            }
+        }
+        if self.trace.is_none() || waiting {
+            return self.execute(instruction);
+        }
+
+        let (ic, before) = (self.ic, self.registers.clone());
+        let output = self.execute(instruction)?;
+        let received = match instruction {
+            Instruction::Rcv(r) if self.dialect == Dialect::Duet => Some(self.registers[r]),
+            _ => None
+        };
+        if let Some(ref mut trace) = self.trace {
+            trace.record(ic, &instruction, &before, &self.registers, output, received);
+        }
+        Ok(output)
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> Result<Option<i64>, Error> {
+        if let Some(&Some(fused)) = self.fused.get(self.ic as usize) {
+            if self.execute_fused(fused) {
+                return Ok(None);
+            }
+        }
+
+        match instruction {
+            Instruction::Snd(x) => {
+                self.ic += 1;
+                if self.dialect == Dialect::Sound {
+                    self.last_sound = Some(self.value(x));
+                    return Ok(None);
+                }
+                return Ok(Some(self.value(x)));
+            },
+            Instruction::Set(r, y) => self.registers[r] = self.value(y),
+            Instruction::Add(r, y) | Instruction::Sub(r, y) | Instruction::Mul(r, y) | Instruction::Mod(r, y) => {
+                let (lhs, rhs) = (self.registers[r], self.value(y));
+                let result = match instruction {
+                    Instruction::Add(..) => self.arithmetic.add(lhs, rhs),
+                    Instruction::Sub(..) => self.arithmetic.sub(lhs, rhs),
+                    Instruction::Mul(..) => self.arithmetic.mul(lhs, rhs),
+                    _ => self.arithmetic.modulo(lhs, rhs),
+                };
+                self.registers[r] = result.map_err(|kind| self.fault(instruction, kind))?;
+            },
+            Instruction::Rcv(r) if self.dialect == Dialect::Sound => {
+                self.ic += 1;
+                if self.registers[r] != 0 {
+                    return Ok(self.last_sound);
+                }
+                return Ok(None);
+            },
+            Instruction::Rcv(r) => {
+                if let Some(v) = self.messages.pop_front() {
+                    self.registers[r] = v;
+                    self.ic += 1;
+                }
+                return Ok(None);
+            },
+            Instruction::Jgz(x, y) => {
+                let condition = self.value(x) > 0;
+                self.jump(instruction, condition, y)?;
+                return Ok(None);
+            },
+            Instruction::Jnz(x, y) => {
+                let condition = self.value(x) != 0;
+                self.jump(instruction, condition, y)?;
+                return Ok(None);
+            }
+        }
+
+        self.ic += 1;
+        Ok(None)
+    }
+
+    /// Executes until the program terminates, waits for a message or ran `limit` instructions.
+    /// Sent and recovered values are dropped, returns the number of executed instructions.
+    /// When tracing, the trace records why the run stopped.
+    pub fn run(&mut self, limit: u64) -> Result<u64, Error> {
+        let mut executed = 0;
+        while executed < limit && !self.terminated() && !self.waiting_message() {
+            if let Err(error) = self.execute_next_instruction() {
+                self.end_trace(End::Error(error.to_string()));
+                return Err(error);
+            }
+            executed += 1;
+        }
+
+        let end = if self.terminated() {
+            End::Terminated
+        }
+        else if self.waiting_message() {
+            End::Waiting
+        }
+        else {
+            End::Limit
+        };
+        self.end_trace(end);
+        Ok(executed)
+    }
+
+    fn end_trace(&mut self, end: End) {
+        if let Some(ref mut trace) = self.trace {
+            trace.end = Some(end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assembler() {
+        let mut assembler = Assembler::new(0, Dialect::Duet);
+        let prog = "
+            set a 1
+            add a 2
+            mul a a
+            mod a 5
+            snd a
+            set a 0
+            rcv a
+            jgz a -1
+            set a 1
+            jgz a -2
+        ";
+        let diagnostics = assembler.load_program_str("set a 1\nadd 2 a").unwrap_err();
+        assert_eq!(diagnostics.0.len(), 1);
+        assert!(assembler.instructions.is_empty());
+        assembler.load_program_str(prog).unwrap();
+        assert_eq!(assembler.register('a'), 0);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.register('a'), 1);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.register('a'), 3);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.register('a'), 9);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.register('a'), 4);
+        assert_eq!(assembler.execute_next_instruction().unwrap(), Some(4));
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.register('a'), 0);
+        assert_eq!(assembler.execute_next_instruction().unwrap(), None);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.ic, 6);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.register('a'), 0);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.ic, 6);
+        assembler.execute_next_instruction().unwrap();
+        assert_eq!(assembler.ic, 6);
+        assembler.execute_next_instruction().unwrap();
+    }
+
+    #[test]
+    fn test_dialects() {
+        let program = assemble_dialect("set a 2\nsnd a\nrcv b\nrcv a\nsnd 5", Dialect::Sound).unwrap();
+        let mut sound = Assembler::with_program(0, Dialect::Sound, &program).unwrap();
+        assert_eq!(sound.execute_next_instruction().unwrap(), None);
+        assert_eq!(sound.execute_next_instruction().unwrap(), None);
+        assert_eq!(sound.execute_next_instruction().unwrap(), None);
+        assert!(!sound.waiting_message());
+        assert_eq!(sound.execute_next_instruction().unwrap(), Some(2));
+        assert_eq!(sound.register('a'), 2);
+
+        let mut duet = Assembler::with_program(0, Dialect::Duet, &program).unwrap();
+        duet.execute_next_instruction().unwrap();
+        assert_eq!(duet.execute_next_instruction().unwrap(), Some(2));
+        assert!(duet.waiting_message());
+
+        assert_eq!(Assembler::with_program(0, Dialect::Coprocessor, &program).err().unwrap().to_string(),
+                   "invalid state: ic 1: snd a is not part of the coprocessor dialect");
+        let mut coprocessor = Assembler::new(0, Dialect::Coprocessor);
+        assert_eq!(coprocessor.load_program_str("set a 2\nadd a 1\njgz a 2").unwrap_err().0.len(), 2);
+        coprocessor.load_program_str("set a 2\nsub a 1\njnz a -1").unwrap();
+        assert_eq!(coprocessor.run(100).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_initial_registers() {
+        let program = assemble_dialect("mul count q
+add total count
+snd total", Dialect::Duet).unwrap();
+        let (count, total) = (named_register("count").unwrap(), named_register("total").unwrap());
+        let initial = [(count, 4), (register('q').unwrap(), 3), (total, 1)].iter().cloned().collect();
+        let mut assembler = Assembler::with_initial(initial, Dialect::Duet, &program).unwrap();
+        assert_eq!(assembler.run(2).unwrap(), 2);
+        assert_eq!(assembler.execute_next_instruction().unwrap(), Some(13));
+        assert_eq!(assembler.registers.to_string(), "q=3 count=12 total=13");
+        assert_eq!(assembler.register('p'), 0);
+    }
+}
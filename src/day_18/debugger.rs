@@ -0,0 +1,429 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use error::Error;
+use super::assembler::Assembler;
+use super::instruction::{Register, named_register, register_name};
+use super::snapshot::Snapshot;
+
+const HELP: &str = "\
+step [n]            execute n instructions (s)
+continue            run until a breakpoint, a rcv on an empty queue, the end or the step limit (c)
+break <line>        stop before the instruction on a source line (b)
+break <r> <op> <v>  stop once register r compares to v, op is one of == != < <= > >=
+delete <n>          remove breakpoint n (d)
+breakpoints         list breakpoints
+watch <r>           print register r at every stop (w)
+unwatch <r>         stop watching register r
+regs                print the non-zero registers (r)
+set <r> <v>         store v in register r
+queue               show received messages waiting in the queue and the sent ones (q)
+push <v>            deliver message v to the program
+list [n]            show n instructions around the current one (l)
+save <file>         write the machine state to a snapshot file
+load <file>         resume the machine state saved in a snapshot file
+quit                leave the debugger";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Breakpoint {
+    /// Stops before the instruction on a 1-based source line.
+    Line(usize),
+    /// Stops once the comparison between a register and a value becomes true.
+    Condition(Register, Comparison, i64),
+}
+
+/// Interactive front end stepping an `Assembler` one instruction at a time.
+pub struct Debugger {
+    pub assembler: Assembler,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<Register>,
+    sent: Vec<i64>,
+    steps: u64,
+    limit: u64,
+    last_command: String,
+}
+
+impl Comparison {
+    fn parse(op: &str) -> Option<Comparison> {
+        match op {
+            "==" => Some(Comparison::Eq),
+            "!=" => Some(Comparison::Ne),
+            "<" => Some(Comparison::Lt),
+            "<=" => Some(Comparison::Le),
+            ">" => Some(Comparison::Gt),
+            ">=" => Some(Comparison::Ge),
+            _ => None,
+        }
+    }
+
+    fn holds(&self, lhs: i64, rhs: i64) -> bool {
+        match *self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl Breakpoint {
+    fn parse(args: &[&str]) -> Result<Breakpoint, String> {
+        match *args {
+            [line] => match line.parse::<usize>() {
+                Ok(line) if line > 0 => Ok(Breakpoint::Line(line)),
+                _ => Err(format!("invalid line `{}`", line)),
+            },
+            [reg, op, value] => {
+                let reg = parse_register(reg)?;
+                let op = Comparison::parse(op).ok_or_else(|| format!("invalid comparison `{}`", op))?;
+                Ok(Breakpoint::Condition(reg, op, parse_value(value)?))
+            },
+            _ => Err(String::from("usage: break <line> | break <r> <op> <v>")),
+        }
+    }
+}
+
+fn parse_register(name: &str) -> Result<Register, String> {
+    named_register(name).ok_or_else(|| format!("invalid register `{}`", name))
+}
+
+fn parse_value(value: &str) -> Result<i64, String> {
+    value.parse().map_err(|_| format!("invalid value `{}`", value))
+}
+
+impl Debugger {
+    pub fn new(assembler: Assembler) -> Debugger {
+        Debugger {
+            assembler,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            sent: Vec::new(),
+            steps: 0,
+            limit: 10_000_000,
+            last_command: String::new(),
+        }
+    }
+
+    /// Stops `continue` after `limit` instructions, so that a program looping forever hands control back.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Values sent by the program so far.
+    pub fn sent(&self) -> &[i64] {
+        &self.sent
+    }
+
+    /// Reads commands until `quit` or the end of `input`, an empty line repeats the last command.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        writeln!(output, "{}", self.context(1))?;
+        write!(output, "(debug) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            let command = match line.trim() {
+                "" => self.last_command.clone(),
+                command => command.to_string(),
+            };
+            if command == "quit" || command == "exit" {
+                break;
+            }
+
+            let reply = self.eval(&command);
+            if !reply.is_empty() {
+                writeln!(output, "{}", reply)?;
+            }
+            self.last_command = command;
+            write!(output, "(debug) ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single command and returns what it prints.
+    pub fn eval(&mut self, command: &str) -> String {
+        let tokens = command.split_whitespace().collect::<Vec<_>>();
+        let (name, args) = match tokens.split_first() {
+            Some((name, args)) => (*name, args),
+            None => return String::new(),
+        };
+        let reply = match name {
+            "step" | "s" => match args.first().map_or(Ok(1), |count| count.parse::<u64>()) {
+                Ok(count) => Ok(self.resume(Some(count))),
+                Err(_) => Err(String::from("usage: step [n]")),
+            },
+            "continue" | "c" => Ok(self.resume(None)),
+            "break" | "b" => match Breakpoint::parse(args) {
+                Ok(Breakpoint::Line(line)) if self.assembler.instruction_on_line(line).is_none() => {
+                    Err(format!("no instruction on line {}", line))
+                },
+                Ok(breakpoint) => {
+                    self.breakpoints.push(breakpoint);
+                    Ok(format!("breakpoint {}: {}", self.breakpoints.len(), breakpoint))
+                },
+                Err(message) => Err(message),
+            },
+            "delete" | "d" => match args.first().and_then(|idx| idx.parse::<usize>().ok()) {
+                Some(idx) if idx > 0 && idx <= self.breakpoints.len() => {
+                    Ok(format!("deleted breakpoint {}", self.breakpoints.remove(idx - 1)))
+                },
+                _ => Err(String::from("usage: delete <n>, see breakpoints")),
+            },
+            "breakpoints" => Ok(self.breakpoints.iter()
+                                    .enumerate()
+                                    .map(|(idx, breakpoint)| format!("{}: {}", idx + 1, breakpoint))
+                                    .collect::<Vec<_>>()
+                                    .join("\n")),
+            "watch" | "w" => args.iter().map(|name| parse_register(name)).collect::<Result<Vec<_>, _>>().map(|regs| {
+                for reg in regs {
+                    if !self.watches.contains(&reg) {
+                        self.watches.push(reg);
+                    }
+                }
+                self.watched()
+            }),
+            "unwatch" => args.iter().map(|name| parse_register(name)).collect::<Result<Vec<_>, _>>().map(|regs| {
+                self.watches.retain(|reg| !regs.contains(reg));
+                self.watched()
+            }),
+            "regs" | "r" => Ok(self.registers()),
+            "set" => match *args {
+                [reg, value] => parse_register(reg).and_then(|reg| {
+                    self.assembler.registers[reg] = parse_value(value)?;
+                    Ok(format!("{} = {}", register_name(reg), self.assembler.registers[reg]))
+                }),
+                _ => Err(String::from("usage: set <r> <v>")),
+            },
+            "queue" | "q" => Ok(self.queue()),
+            "push" => match *args {
+                [value] => parse_value(value).map(|value| {
+                    self.assembler.push_message(value);
+                    self.queue()
+                }),
+                _ => Err(String::from("usage: push <v>")),
+            },
+            "list" | "l" => match args.first().map_or(Ok(3), |radius| radius.parse::<usize>()) {
+                Ok(radius) => Ok(self.context(radius)),
+                Err(_) => Err(String::from("usage: list [n]")),
+            },
+            "save" => match *args {
+                [path] => File::create(path).and_then(|file| self.assembler.snapshot().write(BufWriter::new(file)))
+                                            .map(|()| format!("saved to {}", path))
+                                            .map_err(|error| format!("failed to write {}: {}", path, error)),
+                _ => Err(String::from("usage: save <file>")),
+            },
+            "load" => match *args {
+                [path] => File::open(path).map_err(Error::from)
+                                          .and_then(|file| Snapshot::read(BufReader::new(file)))
+                                          .and_then(Assembler::from_snapshot)
+                                          .map(|assembler| {
+                                              self.assembler = assembler;
+                                              format!("loaded {}\n{}", path, self.context(1))
+                                          })
+                                          .map_err(|error| format!("failed to load {}: {}", path, error)),
+                _ => Err(String::from("usage: load <file>")),
+            },
+            "help" | "h" => Ok(String::from(HELP)),
+            _ => Err(format!("unknown command `{}`, try help", name)),
+        };
+        reply.unwrap_or_else(|message| message)
+    }
+
+    fn conditions(&self) -> Vec<bool> {
+        self.breakpoints.iter().map(|breakpoint| self.triggered(breakpoint)).collect()
+    }
+
+    fn triggered(&self, breakpoint: &Breakpoint) -> bool {
+        match *breakpoint {
+            Breakpoint::Line(line) => self.assembler.line(self.assembler.ic) == Some(line),
+            Breakpoint::Condition(reg, op, value) => op.holds(self.assembler.registers[reg], value),
+        }
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        if let Some(value) = self.assembler.execute_next_instruction()? {
+            self.sent.push(value);
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    /// Executes up to `count` instructions, or until something stops the program when `None`, at most the step limit.
+    fn resume(&mut self, count: Option<u64>) -> String {
+        let mut executed = 0;
+        let reason = loop {
+            if self.assembler.terminated() {
+                break String::from("program terminated");
+            }
+            if self.assembler.waiting_message() {
+                break String::from("waiting for a message, use push <v>");
+            }
+            if count == Some(executed) {
+                break format!("step {}", self.steps);
+            }
+            if count.is_none() && executed == self.limit {
+                break format!("step limit reached after {} instructions, step {}", executed, self.steps);
+            }
+
+            let before = self.conditions();
+            if let Err(error) = self.advance() {
+                break format!("error: {}", error);
+            }
+            executed += 1;
+
+            let after = self.conditions();
+            if let Some(idx) = (0 .. after.len()).find(|&idx| after[idx] && !before[idx]) {
+                break format!("breakpoint {}: {}", idx + 1, self.breakpoints[idx]);
+            }
+        };
+
+        let mut reply = format!("{}\n{}", reason, self.context(1));
+        if !self.watches.is_empty() {
+            reply.push('\n');
+            reply.push_str(&self.watched());
+        }
+        reply
+    }
+
+    /// Current instruction with `radius` instructions before and after it.
+    pub fn context(&self, radius: usize) -> String {
+        let program = &self.assembler.instructions;
+        if self.assembler.terminated() {
+            return format!("terminated at ic {} after {} steps", self.assembler.ic, self.steps);
+        }
+
+        let ic = self.assembler.ic as usize;
+        let first = ic.saturating_sub(radius);
+        let last = (ic + radius + 1).min(program.len());
+        (first .. last).map(|idx| {
+                           let marker = if idx == ic { "=>" } else { "  " };
+                           let line = self.assembler.line(idx as i64).unwrap_or(idx + 1);
+                           let stop = if self.breakpoints.contains(&Breakpoint::Line(line)) { "*" } else { " " };
+                           format!("{}{}{:>4}  {}", marker, stop, line, program[idx])
+                       })
+                       .collect::<Vec<_>>()
+                       .join("\n")
+    }
+
+    fn watched(&self) -> String {
+        let values = self.watches.iter()
+                                 .map(|&reg| format!("{}={}", register_name(reg), self.assembler.registers[reg]))
+                                 .collect::<Vec<_>>();
+        format!("watch: {}", values.join(" "))
+    }
+
+    fn registers(&self) -> String {
+        if self.assembler.registers.iter().next().is_none() {
+            String::from("all registers are 0")
+        }
+        else {
+            self.assembler.registers.to_string()
+        }
+    }
+
+    fn queue(&self) -> String {
+        let received = self.assembler.messages().iter().map(|value| value.to_string()).collect::<Vec<_>>();
+        let sent = self.sent.iter().rev().take(8).rev().map(|value| value.to_string()).collect::<Vec<_>>();
+        format!("queue: [{}]\nsent {}: [{}{}]",
+                received.join(", "), self.sent.len(),
+                if self.sent.len() > 8 { "..., " } else { "" }, sent.join(", "))
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match *self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Breakpoint::Line(line) => write!(f, "line {}", line),
+            Breakpoint::Condition(reg, op, value) => write!(f, "{} {} {}", register_name(reg), op, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+    use super::super::diagnostic::assemble;
+    use super::super::dialect::Dialect;
+
+    const PROGRAM: &str = "set a 3\nadd b a\nadd a -1\njgz a -2\nsnd b\nrcv c\nadd c 1";
+
+    #[test]
+    fn test_debugger() {
+        let mut debugger = Debugger::new(Assembler::with_program(0, Dialect::Duet, &assemble(PROGRAM).unwrap()).unwrap());
+        assert_eq!(debugger.eval("step"), "step 1\n      1  set a 3\n=>    2  add b a\n      3  add a -1");
+        assert_eq!(debugger.eval("b a < 2"), "breakpoint 1: a < 2");
+        assert_eq!(debugger.eval("b 5"), "breakpoint 2: line 5");
+        assert_eq!(debugger.eval("w b a"), "watch: b=0 a=3");
+        assert_eq!(debugger.eval("c"), "breakpoint 1: a < 2\n      3  add a -1\n=>    4  jgz a -2\n  *   5  snd b\nwatch: b=5 a=1");
+        assert!(debugger.eval("c").starts_with("breakpoint 2: line 5"));
+        assert!(debugger.eval("c").starts_with("waiting for a message"));
+        assert_eq!(debugger.eval("push 7"), "queue: [7]\nsent 1: [6]");
+        assert_eq!(debugger.eval("d 1"), "deleted breakpoint a < 2");
+        assert!(debugger.eval("c").starts_with("program terminated"));
+        assert_eq!(debugger.eval("regs"), "b=6 c=8");
+        assert_eq!(debugger.eval("break A > 1"), "invalid register `A`");
+        assert_eq!(debugger.eval("jump"), "unknown command `jump`, try help");
+
+        let mut assembler = Assembler::new(0, Dialect::Duet);
+        assembler.load_program_str("set a 1\n\nadd a 2\n\njgz a 0").unwrap();
+        let mut debugger = Debugger::new(assembler);
+        debugger.set_limit(100);
+        assert_eq!(debugger.eval("b 2"), "no instruction on line 2");
+        assert_eq!(debugger.eval("b 3"), "breakpoint 1: line 3");
+        assert!(debugger.eval("c").starts_with("breakpoint 1: line 3\n      1  set a 1\n=>*   3  add a 2"));
+        assert!(debugger.eval("c").starts_with("step limit reached after 100 instructions, step 101\n  *   3  add a 2\n=>    5  jgz a 0"));
+        assert!(debugger.eval("c").starts_with("step limit reached after 100 instructions, step 201"));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = env::temp_dir().join(format!("debugger-{}.snapshot", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut debugger = Debugger::new(Assembler::with_program(0, Dialect::Duet, &assemble(PROGRAM).unwrap()).unwrap());
+        debugger.eval("step 4");
+        assert_eq!(debugger.eval(&format!("save {}", path)), format!("saved to {}", path));
+        debugger.eval("c");
+        assert!(debugger.eval(&format!("load {}", path)).starts_with(&format!("loaded {}\n      1  set a 3\n=>    2  add b a", path)));
+        assert_eq!(debugger.eval("regs"), "a=2 b=3");
+        fs::remove_file(path).unwrap();
+        assert!(debugger.eval(&format!("load {}", path)).starts_with("failed to load"));
+    }
+
+    #[test]
+    fn test_run() {
+        let mut debugger = Debugger::new(Assembler::with_program(0, Dialect::Duet, &assemble(PROGRAM).unwrap()).unwrap());
+        let mut output = Vec::new();
+        debugger.run("set a 1\nstep 2\n\nquit\nstep\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("(debug) a = 1\n(debug) step 2\n"));
+        assert!(output.contains("(debug) step 4\n"));
+        assert!(!output.contains("step 5"));
+    }
+}
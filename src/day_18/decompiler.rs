@@ -0,0 +1,453 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use super::instruction::{Instruction, Operand, register_name};
+
+/// Straight-line run of instructions `start .. end`, only the last one may jump.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    /// Indices of the blocks control can continue to.
+    pub successors: Vec<usize>,
+    /// Control can leave the program after this block.
+    pub exits: bool,
+    /// Ends with a jump by a register, whose target is unknown.
+    pub computed: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+}
+
+/// How a jump instruction behaves, offsets held in registers are not followed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Jump {
+    Never,
+    Always(i64),
+    When(Condition, i64),
+    Computed,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Condition {
+    value: Operand,
+    positive: bool,
+    negated: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Stmt {
+    Op(usize, Instruction),
+    If(Condition, Vec<Stmt>, Vec<Stmt>),
+    Loop(Vec<Stmt>),
+    DoWhile(Vec<Stmt>, Condition),
+    Break,
+    Continue,
+    Goto(Option<Condition>, i64),
+}
+
+/// Targets of `break` and `continue` in the innermost loop.
+#[derive(Clone, Copy)]
+struct Scope {
+    next: usize,
+    exit: usize,
+}
+
+fn jump(ic: usize, instruction: &Instruction) -> Option<Jump> {
+    let (value, offset, positive) = match *instruction {
+        Instruction::Jgz(x, y) => (x, y, true),
+        Instruction::Jnz(x, y) => (x, y, false),
+        _ => return None,
+    };
+    let target = match offset {
+        Operand::Const(offset) => ic as i64 + offset,
+        Operand::Reg(_) => return Some(Jump::Computed),
+    };
+    Some(match value {
+        Operand::Const(c) if (positive && c > 0) || (!positive && c != 0) => Jump::Always(target),
+        Operand::Const(_) => Jump::Never,
+        Operand::Reg(_) => Jump::When(Condition { value, positive, negated: false }, target),
+    })
+}
+
+impl Condition {
+    fn negate(self) -> Condition {
+        Condition { negated: !self.negated, ..self }
+    }
+}
+
+impl ControlFlowGraph {
+    pub fn new(program: &[Instruction]) -> ControlFlowGraph {
+        let len = program.len();
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for (ic, instruction) in program.iter().enumerate() {
+            match jump(ic, instruction) {
+                Some(Jump::Always(target)) | Some(Jump::When(_, target)) => {
+                    if target >= 0 && (target as usize) < len {
+                        leaders.insert(target as usize);
+                    }
+                    leaders.insert(ic + 1);
+                },
+                Some(Jump::Computed) => {
+                    leaders.insert(ic + 1);
+                },
+                _ => (),
+            }
+        }
+        let starts = leaders.into_iter().filter(|&start| start < len).collect::<Vec<_>>();
+        let block_of = |target: i64| {
+            if target >= 0 && (target as usize) < len {
+                starts.binary_search(&(target as usize)).ok()
+            }
+            else {
+                None
+            }
+        };
+
+        let blocks = starts.iter().enumerate().map(|(idx, &start)| {
+            let end = starts.get(idx + 1).cloned().unwrap_or(len);
+            let last = end - 1;
+            let mut targets = Vec::new();
+            let mut computed = false;
+            match jump(last, &program[last]) {
+                Some(Jump::Always(target)) => targets.push(target),
+                Some(Jump::When(_, target)) => targets.extend(vec![last as i64 + 1, target]),
+                Some(Jump::Computed) => computed = true,
+                _ => targets.push(last as i64 + 1),
+            }
+            BasicBlock {
+                start,
+                end,
+                successors: targets.iter().filter_map(|&target| block_of(target)).collect(),
+                exits: targets.iter().any(|&target| block_of(target).is_none()),
+                computed,
+            }
+        }).collect();
+        ControlFlowGraph { blocks }
+    }
+
+    /// Jumps to an earlier or the same block as `(jump instruction, target instruction)`.
+    pub fn back_edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        for block in &self.blocks {
+            for &successor in &block.successors {
+                let target = self.blocks[successor].start;
+                if target <= block.start {
+                    edges.push((block.end - 1, target));
+                }
+            }
+        }
+        edges
+    }
+}
+
+struct Decompiler<'a> {
+    program: &'a [Instruction],
+    back_edges: Vec<(usize, usize)>,
+}
+
+impl<'a> Decompiler<'a> {
+    /// Structures instructions `lo .. hi`, jumps leaving the range become `break`, `continue` or `goto`.
+    fn structure(&self, lo: usize, hi: usize, scope: Option<Scope>) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+        let mut ic = lo;
+        while ic < hi {
+            let back_edge = self.back_edges.iter()
+                                           .filter(|&&(from, to)| to == ic && from < hi)
+                                           .map(|&(from, _)| from)
+                                           .max();
+            if let Some(end) = back_edge {
+                match jump(end, &self.program[end]) {
+                    Some(Jump::When(condition, _)) => {
+                        let body = self.structure(ic, end, Some(Scope { next: end, exit: end + 1 }));
+                        stmts.push(Stmt::DoWhile(body, condition));
+                    },
+                    _ => stmts.push(Stmt::Loop(self.structure(ic, end, Some(Scope { next: ic, exit: end + 1 })))),
+                }
+                ic = end + 1;
+                continue;
+            }
+
+            let instruction = self.program[ic];
+            match jump(ic, &instruction) {
+                None | Some(Jump::Computed) => stmts.push(Stmt::Op(ic, instruction)),
+                Some(Jump::Never) => (),
+                Some(Jump::Always(target)) => {
+                    if target != ic as i64 + 1 {
+                        stmts.push(self.leave(None, target, scope));
+                    }
+                },
+                Some(Jump::When(condition, target)) => {
+                    if target > ic as i64 + 1 && target <= hi as i64 {
+                        let target = target as usize;
+                        let other = match jump(target - 1, &self.program[target - 1]) {
+                            Some(Jump::Always(other)) if target - 1 > ic && other > target as i64 && other <= hi as i64 => {
+                                Some(other as usize)
+                            },
+                            _ => None,
+                        };
+                        match other {
+                            Some(other) => {
+                                let then = self.structure(ic + 1, target - 1, scope);
+                                let otherwise = self.structure(target, other, scope);
+                                if then.is_empty() {
+                                    stmts.push(Stmt::If(condition, otherwise, Vec::new()));
+                                }
+                                else {
+                                    stmts.push(Stmt::If(condition.negate(), then, otherwise));
+                                }
+                                ic = other;
+                            },
+                            None => {
+                                stmts.push(Stmt::If(condition.negate(), self.structure(ic + 1, target, scope), Vec::new()));
+                                ic = target;
+                            },
+                        }
+                        continue;
+                    }
+                    stmts.push(self.leave(Some(condition), target, scope));
+                },
+            }
+            ic += 1;
+        }
+        stmts
+    }
+
+    /// Jump out of the structured region, as `break`/`continue` where the innermost loop allows it.
+    fn leave(&self, condition: Option<Condition>, target: i64, scope: Option<Scope>) -> Stmt {
+        let jump = match scope {
+            Some(scope) if target == scope.exit as i64 => Stmt::Break,
+            Some(scope) if target == scope.next as i64 => Stmt::Continue,
+            _ => return Stmt::Goto(condition, target),
+        };
+        match condition {
+            Some(condition) => Stmt::If(condition, vec![jump], Vec::new()),
+            None => jump,
+        }
+    }
+}
+
+/// Lifts `program` into structured pseudocode, jumps that do not fit a loop or a conditional become `goto`s.
+pub fn decompile(program: &[Instruction]) -> String {
+    let decompiler = Decompiler { program, back_edges: ControlFlowGraph::new(program).back_edges() };
+    let stmts = decompiler.structure(0, program.len(), None);
+
+    let mut labels = BTreeSet::new();
+    collect_labels(&stmts, &mut labels);
+    let mut text = String::new();
+    render(&stmts, 0, &mut labels, program.len(), &mut text);
+    text
+}
+
+fn collect_labels(stmts: &[Stmt], labels: &mut BTreeSet<i64>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Goto(_, target) => {
+                labels.insert(*target);
+            },
+            Stmt::If(_, then, otherwise) => {
+                collect_labels(then, labels);
+                collect_labels(otherwise, labels);
+            },
+            Stmt::Loop(body) | Stmt::DoWhile(body, _) => collect_labels(body, labels),
+            _ => (),
+        }
+    }
+}
+
+fn first_ic(stmts: &[Stmt]) -> Option<usize> {
+    stmts.iter().filter_map(|stmt| match stmt {
+        Stmt::Op(ic, _) => Some(*ic),
+        Stmt::Loop(body) | Stmt::DoWhile(body, _) => first_ic(body),
+        _ => None,
+    }).next()
+}
+
+/// Writes `stmts` indented by `depth`, each label in `labels` is placed once and then removed.
+fn render(stmts: &[Stmt], depth: usize, labels: &mut BTreeSet<i64>, len: usize, text: &mut String) {
+    let indent = "    ".repeat(depth);
+    for stmt in stmts {
+        let label = match stmt {
+            Stmt::Op(ic, _) => Some(*ic),
+            Stmt::Loop(body) | Stmt::DoWhile(body, _) => first_ic(body),
+            _ => None,
+        };
+        if let Some(ic) = label.filter(|&ic| labels.remove(&(ic as i64))) {
+            let _ = writeln!(text, "{}line_{}:", indent, ic + 1);
+        }
+
+        let _ = match stmt {
+            Stmt::Op(_, instruction) => writeln!(text, "{}{}", indent, statement(instruction)),
+            Stmt::If(condition, then, otherwise) => {
+                let _ = writeln!(text, "{}if {} {{", indent, expression(condition));
+                render(then, depth + 1, labels, len, text);
+                if !otherwise.is_empty() {
+                    let _ = writeln!(text, "{}}} else {{", indent);
+                    render(otherwise, depth + 1, labels, len, text);
+                }
+                writeln!(text, "{}}}", indent)
+            },
+            Stmt::Loop(body) => {
+                let _ = writeln!(text, "{}loop {{", indent);
+                render(body, depth + 1, labels, len, text);
+                writeln!(text, "{}}}", indent)
+            },
+            Stmt::DoWhile(body, condition) => {
+                let _ = writeln!(text, "{}do {{", indent);
+                render(body, depth + 1, labels, len, text);
+                writeln!(text, "{}}} while {}", indent, expression(condition))
+            },
+            Stmt::Break => writeln!(text, "{}break", indent),
+            Stmt::Continue => writeln!(text, "{}continue", indent),
+            Stmt::Goto(condition, target) => {
+                let destination = if *target < 0 || *target >= len as i64 {
+                    String::from("halt")
+                }
+                else {
+                    format!("goto line_{}", target + 1)
+                };
+                match condition {
+                    Some(condition) => writeln!(text, "{}if {} {{ {} }}", indent, expression(condition), destination),
+                    None => writeln!(text, "{}{}", indent, destination),
+                }
+            },
+        };
+    }
+
+    if depth == 0 && labels.contains(&(len as i64)) {
+        let _ = writeln!(text, "line_{}:", len + 1);
+    }
+}
+
+fn expression(condition: &Condition) -> String {
+    let op = match (condition.positive, condition.negated) {
+        (true, false) => ">",
+        (true, true) => "<=",
+        (false, false) => "!=",
+        (false, true) => "==",
+    };
+    format!("{} {} 0", condition.value, op)
+}
+
+fn statement(instruction: &Instruction) -> String {
+    match *instruction {
+        Instruction::Set(r, y) => format!("{} = {}", register_name(r), y),
+        Instruction::Add(r, Operand::Const(c)) if c < 0 => format!("{} -= {}", register_name(r), -(c as i128)),
+        Instruction::Add(r, y) => format!("{} += {}", register_name(r), y),
+        Instruction::Sub(r, Operand::Const(c)) if c < 0 => format!("{} += {}", register_name(r), -(c as i128)),
+        Instruction::Sub(r, y) => format!("{} -= {}", register_name(r), y),
+        Instruction::Mul(r, y) => format!("{} *= {}", register_name(r), y),
+        Instruction::Mod(r, y) => format!("{} %= {}", register_name(r), y),
+        Instruction::Jgz(..) | Instruction::Jnz(..) => format!("{}  // computed jump", instruction),
+        Instruction::Snd(_) | Instruction::Rcv(_) => instruction.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::assemble;
+
+    #[test]
+    fn test_control_flow_graph() {
+        let program = assemble("set a 2\nsub a 1\njnz a -1\njgz b 2\nsnd a").unwrap();
+        let cfg = ControlFlowGraph::new(&program);
+        let blocks = cfg.blocks.iter().map(|block| (block.start, block.end, block.successors.clone(), block.exits)).collect::<Vec<_>>();
+        assert_eq!(blocks, vec![(0, 1, vec![1], false), (1, 3, vec![2, 1], false), (3, 4, vec![3], true), (4, 5, vec![], true)]);
+        assert_eq!(cfg.back_edges(), vec![(2, 1)]);
+    }
+
+    #[test]
+    fn test_decompile() {
+        let program = assemble("\
+            set b 81
+            set c b
+            jnz a 2
+            jnz 1 5
+            mul b 100
+            sub b -100000
+            set c b
+            sub c -17000
+            set f 1
+            set d 2
+            set e 2
+            set g d
+            mul g e
+            sub g b
+            jnz g 2
+            set f 0
+            sub e -1
+            set g e
+            sub g b
+            jnz g -8
+            sub d -1
+            set g d
+            sub g b
+            jnz g -13
+            jnz f 2
+            sub h -1
+            set g b
+            sub g c
+            jnz g 2
+            jnz 1 3
+            sub b -17
+            jnz 1 -23").unwrap();
+        assert_eq!(decompile(&program), "\
+b = 81
+c = b
+if a != 0 {
+    b *= 100
+    b += 100000
+    c = b
+    c += 17000
+}
+loop {
+    f = 1
+    d = 2
+    do {
+        e = 2
+        do {
+            g = d
+            g *= e
+            g -= b
+            if g == 0 {
+                f = 0
+            }
+            e += 1
+            g = e
+            g -= b
+        } while g != 0
+        d += 1
+        g = d
+        g -= b
+    } while g != 0
+    if f == 0 {
+        h += 1
+    }
+    g = b
+    g -= c
+    if g == 0 {
+        break
+    }
+    b += 17
+}
+");
+    }
+
+    #[test]
+    fn test_decompile_goto() {
+        let program = assemble("jgz a 2\nset b 1\nadd b 1\njgz b -2\njgz c 3\njgz c p").unwrap();
+        assert_eq!(decompile(&program), "\
+if a <= 0 {
+    line_2:
+    b = 1
+}
+b += 1
+if b > 0 { goto line_2 }
+if c > 0 { halt }
+jgz c p  // computed jump
+");
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+use error::{self, Error};
+use super::dialect::Dialect;
+use super::instruction::Instruction;
+
+pub const OPCODES: [&str; 9] = ["snd", "set", "add", "sub", "mul", "mod", "rcv", "jgz", "jnz"];
+
+/// Problem found on a single line while assembling a program.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+    pub hint: String,
+}
+
+/// Every problem of a program, ordered by line.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostic {
+    pub fn unknown_opcode(line: usize, column: usize, token: &str) -> Diagnostic {
+        let hint = match OPCODES.iter().find(|op| distance(op, token) <= 1) {
+            Some(op) => format!("did you mean `{}`?", op),
+            None => format!("expected one of {}", OPCODES.join(", ")),
+        };
+        Diagnostic::new(line, column, token, "unknown opcode", hint)
+    }
+
+    pub fn unsupported_opcode(line: usize, column: usize, token: &str, dialect: Dialect) -> Diagnostic {
+        Diagnostic::new(line, column, token, &format!("not part of the {} dialect", dialect),
+                        format!("the {} dialect supports {}", dialect, dialect.opcodes().join(", ")))
+    }
+
+    pub fn operand_count(line: usize, column: usize, token: &str, op: &str, expected: usize, found: usize) -> Diagnostic {
+        let usage = if expected == 1 { format!("{} X", op) } else { format!("{} X Y", op) };
+        Diagnostic::new(line, column, token,
+                        &format!("wrong operand count, expected {} found {}", expected, found),
+                        format!("usage: {}", usage))
+    }
+
+    pub fn register_required(line: usize, column: usize, token: &str, op: &str) -> Diagnostic {
+        Diagnostic::new(line, column, token, "register required",
+                        format!("`{}` writes to its first operand, use a register name", op))
+    }
+
+    pub fn invalid_operand(line: usize, column: usize, token: &str) -> Diagnostic {
+        Diagnostic::new(line, column, token, "invalid operand",
+                        "operands are lowercase register names or integer constants")
+    }
+
+    fn new<H: Into<String>>(line: usize, column: usize, token: &str, message: &str, hint: H) -> Diagnostic {
+        Diagnostic {
+            line,
+            column,
+            token: String::from(token),
+            message: String::from(message),
+            hint: hint.into(),
+        }
+    }
+}
+
+/// Number of single character edits between two short words.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0 ..= b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == cb { prev } else { 1 + prev.min(cur).min(row[j]) };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// Checks every line of `text` and returns all problems found.
+pub fn validate(text: &str) -> Diagnostics {
+    match assemble(text) {
+        Ok(_) => Diagnostics::default(),
+        Err(diagnostics) => diagnostics,
+    }
+}
+
+/// Decodes every non-empty line of `text`, or reports all problems found.
+pub fn assemble(text: &str) -> Result<Vec<Instruction>, Diagnostics> {
+    assemble_with(text, None)
+}
+
+/// Line of `text`, counted from 1, of every instruction `assemble` decodes from it.
+pub fn source_lines(text: &str) -> Vec<usize> {
+    numbered_lines(text).map(|(line_no, _)| line_no).collect()
+}
+
+/// Non-empty lines of `text` with their 1-based line number.
+fn numbered_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines().enumerate().filter(|&(_, line)| !line.trim().is_empty()).map(|(idx, line)| (idx + 1, line))
+}
+
+/// Like `assemble`, also rejecting instructions `dialect` does not support.
+pub fn assemble_dialect(text: &str, dialect: Dialect) -> Result<Vec<Instruction>, Diagnostics> {
+    assemble_with(text, Some(dialect))
+}
+
+fn assemble_with(text: &str, dialect: Option<Dialect>) -> Result<Vec<Instruction>, Diagnostics> {
+    let mut program = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (line_no, line) in numbered_lines(text) {
+        match (Instruction::assemble_line(line_no, line), dialect) {
+            (Ok(instruction), Some(dialect)) if !dialect.supports(&instruction) => {
+                let op = line.split_whitespace().next().unwrap_or("");
+                diagnostics.push(Diagnostic::unsupported_opcode(line_no, error::column(line, op), op, dialect));
+            },
+            (Ok(instruction), _) => program.push(instruction),
+            (Err(found), _) => diagnostics.extend(found),
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(program)
+    }
+    else {
+        Err(Diagnostics(diagnostics))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {} `{}` (hint: {})", self.line, self.column, self.message, self.token, self.hint)
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, diagnostic) in self.0.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Diagnostics> for Error {
+    fn from(diagnostics: Diagnostics) -> Error {
+        let mut iter = diagnostics.0.iter();
+        match iter.next() {
+            Some(first) => {
+                let mut message = format!("{} `{}` (hint: {})", first.message, first.token, first.hint);
+                for other in iter {
+                    message.push_str(&format!("\n{}", other));
+                }
+                Error::parse(first.line, first.column, message)
+            },
+            None => Error::parse(1, 1, "empty diagnostics"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let diagnostics = validate("set a 1\njgx a 2\n\nadd 3 b c\nsnd\nmul a ?\n");
+        let lines = diagnostics.0.iter().map(|d| (d.line, d.column, d.token.as_str())).collect::<Vec<_>>();
+        assert_eq!(lines, vec![(2, 1, "jgx"), (4, 5, "3"), (4, 9, "c"), (5, 4, ""), (6, 7, "?")]);
+        assert_eq!(diagnostics.0[0].hint, "did you mean `jgz`?");
+        assert_eq!(diagnostics.0[2].to_string(), "4:9: wrong operand count, expected 2 found 3 `c` (hint: usage: add X Y)");
+        assert_eq!(diagnostics.0[1].message, "register required");
+        assert!(validate("set a 1\njgz a -1").0.is_empty());
+
+        let rejected = assemble_dialect("set a 1\n  snd a\njnz a -1", Dialect::Coprocessor).unwrap_err();
+        assert_eq!(rejected.to_string(), "2:3: not part of the coprocessor dialect `snd` (hint: the coprocessor dialect supports set, sub, mul, jnz)");
+        assert_eq!(assemble_dialect("set a 1\nsub a 1", Dialect::Sound).unwrap_err().0[0].line, 2);
+
+        let error = Error::from(diagnostics);
+        assert!(error.to_string().starts_with("parse error at 2:1: unknown opcode `jgx` (hint: did you mean `jgz`?)\n4:5: register required"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+use super::instruction::Instruction;
+
+/// Instruction set and I/O semantics an `Assembler` runs with.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Dialect {
+    /// `snd` plays a sound, `rcv X` recovers the last one played when `X` is not zero.
+    Sound,
+    /// `snd` sends a value to another program, `rcv` waits for one.
+    Duet,
+    /// `sub` and `jnz` instead of `add`, `mod` and `jgz`, without any I/O.
+    Coprocessor,
+}
+
+impl Dialect {
+    pub fn parse(name: &str) -> Option<Dialect> {
+        match name {
+            "sound" => Some(Dialect::Sound),
+            "duet" => Some(Dialect::Duet),
+            "coprocessor" => Some(Dialect::Coprocessor),
+            _ => None,
+        }
+    }
+
+    /// Coprocessor for programs using `sub` or `jnz`, Duet otherwise.
+    pub fn detect(program: &[Instruction]) -> Dialect {
+        let coprocessor = program.iter().any(|instruction| matches!(instruction, Instruction::Sub(..) | Instruction::Jnz(..)));
+        if coprocessor { Dialect::Coprocessor } else { Dialect::Duet }
+    }
+
+    pub fn opcodes(&self) -> &'static [&'static str] {
+        match *self {
+            Dialect::Sound | Dialect::Duet => &["snd", "set", "add", "mul", "mod", "rcv", "jgz"],
+            Dialect::Coprocessor => &["set", "sub", "mul", "jnz"],
+        }
+    }
+
+    pub fn supports(&self, instruction: &Instruction) -> bool {
+        match (*self, instruction) {
+            (Dialect::Coprocessor, Instruction::Set(..)) | (Dialect::Coprocessor, Instruction::Sub(..)) |
+            (Dialect::Coprocessor, Instruction::Mul(..)) | (Dialect::Coprocessor, Instruction::Jnz(..)) => true,
+            (Dialect::Coprocessor, _) => false,
+            (_, Instruction::Sub(..)) | (_, Instruction::Jnz(..)) => false,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Dialect::Sound => "sound",
+            Dialect::Duet => "duet",
+            Dialect::Coprocessor => "coprocessor",
+        };
+        write!(f, "{}", name)
+    }
+}
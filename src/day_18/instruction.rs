@@ -0,0 +1,231 @@
+use std::fmt;
+use error::{self, Error};
+use super::diagnostic::{Diagnostic, Diagnostics, assemble};
+
+/// Index of a register, `a` to `z` are 0 to 25 and longer names follow in bijective base 26, `aa` is 26.
+pub type Register = usize;
+
+/// Registers with a single letter name.
+pub const LETTERS: usize = 26;
+
+/// Longest register name, so that every index fits in 64 bits.
+pub const MAX_NAME_LEN: usize = 12;
+
+pub fn register(name: char) -> Option<Register> {
+    if name.is_ascii_lowercase() {
+        Some(name as usize - 'a' as usize)
+    }
+    else {
+        None
+    }
+}
+
+/// Register called `name`, made of 1 to `MAX_NAME_LEN` lowercase letters.
+pub fn named_register(name: &str) -> Option<Register> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN || !name.bytes().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    Some(name.bytes().fold(0, |index, c| index * LETTERS + (c - b'a') as usize + 1) - 1)
+}
+
+pub fn register_name(reg: Register) -> String {
+    let mut name = Vec::new();
+    let mut index = reg + 1;
+    while index > 0 {
+        index -= 1;
+        name.push(b'a' + (index % LETTERS) as u8);
+        index /= LETTERS;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Operand {
+    Reg(Register),
+    Const(i64)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Instruction {
+    Snd(Operand),
+    Set(Register, Operand),
+    Add(Register, Operand),
+    Sub(Register, Operand),
+    Mul(Register, Operand),
+    Mod(Register, Operand),
+    Rcv(Register),
+    Jgz(Operand, Operand),
+    Jnz(Operand, Operand),
+}
+
+impl Operand {
+    fn decode(token: &str) -> Option<Operand> {
+        match named_register(token) {
+            Some(reg) => Some(Operand::Reg(reg)),
+            None => token.parse().map(Operand::Const).ok()
+        }
+    }
+}
+
+impl Instruction {
+    /// Decodes a single line of assembly, errors are reported on line 1.
+    pub fn decode(line: &str) -> Result<Instruction, Error> {
+        Instruction::assemble_line(1, line).map_err(|found| Error::from(Diagnostics(found)))
+    }
+
+    /// Decodes line `line_no` of a program, collecting every problem found on it.
+    pub fn assemble_line(line_no: usize, line: &str) -> Result<Instruction, Vec<Diagnostic>> {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let op = match tokens.first() {
+            Some(&op) => op,
+            None => return Err(vec![Diagnostic::unknown_opcode(line_no, 1, "")]),
+        };
+        let arity = match op {
+            "snd" | "rcv" => 1,
+            "set" | "add" | "sub" | "mul" | "mod" | "jgz" | "jnz" => 2,
+            _ => return Err(vec![Diagnostic::unknown_opcode(line_no, error::column(line, op), op)]),
+        };
+
+        let mut diagnostics = Vec::new();
+        let mut operands = Vec::new();
+        for (idx, &token) in tokens.iter().enumerate().skip(1).take(arity) {
+            let column = error::column(line, token);
+            match Operand::decode(token) {
+                Some(Operand::Const(_)) if idx == 1 && !["snd", "jgz", "jnz"].contains(&op) => {
+                    diagnostics.push(Diagnostic::register_required(line_no, column, token, op));
+                },
+                Some(operand) => operands.push(operand),
+                None => diagnostics.push(Diagnostic::invalid_operand(line_no, column, token)),
+            }
+        }
+        if tokens.len() != arity + 1 {
+            let (column, token) = match tokens.get(arity + 1) {
+                Some(token) => (error::column(line, token), *token),
+                None => (line.len() + 1, ""),
+            };
+            diagnostics.push(Diagnostic::operand_count(line_no, column, token, op, arity, tokens.len() - 1));
+        }
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        let target = |operand: Operand| match operand {
+            Operand::Reg(reg) => reg,
+            Operand::Const(_) => unreachable!("constant targets are reported as register_required above"),
+        };
+        Ok(match op {
+            "snd" => Instruction::Snd(operands[0]),
+            "set" => Instruction::Set(target(operands[0]), operands[1]),
+            "add" => Instruction::Add(target(operands[0]), operands[1]),
+            "sub" => Instruction::Sub(target(operands[0]), operands[1]),
+            "mul" => Instruction::Mul(target(operands[0]), operands[1]),
+            "mod" => Instruction::Mod(target(operands[0]), operands[1]),
+            "rcv" => Instruction::Rcv(target(operands[0])),
+            "jgz" => Instruction::Jgz(operands[0], operands[1]),
+            _ => Instruction::Jnz(operands[0], operands[1]),
+        })
+    }
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> &'static str {
+        match *self {
+            Instruction::Snd(_) => "snd",
+            Instruction::Set(..) => "set",
+            Instruction::Add(..) => "add",
+            Instruction::Sub(..) => "sub",
+            Instruction::Mul(..) => "mul",
+            Instruction::Mod(..) => "mod",
+            Instruction::Rcv(_) => "rcv",
+            Instruction::Jgz(..) => "jgz",
+            Instruction::Jnz(..) => "jnz",
+        }
+    }
+
+    /// Register written by the instruction.
+    pub fn writes(&self) -> Option<Register> {
+        match *self {
+            Instruction::Set(r, _) | Instruction::Add(r, _) | Instruction::Sub(r, _) |
+            Instruction::Mul(r, _) | Instruction::Mod(r, _) | Instruction::Rcv(r) => Some(r),
+            Instruction::Snd(_) | Instruction::Jgz(..) | Instruction::Jnz(..) => None,
+        }
+    }
+
+    /// Registers whose value the instruction uses.
+    pub fn reads(&self) -> Vec<Register> {
+        let operands = match *self {
+            Instruction::Snd(x) => vec![x],
+            Instruction::Set(_, y) => vec![y],
+            Instruction::Add(r, y) | Instruction::Sub(r, y) |
+            Instruction::Mul(r, y) | Instruction::Mod(r, y) => vec![Operand::Reg(r), y],
+            Instruction::Rcv(_) => vec![],
+            Instruction::Jgz(x, y) | Instruction::Jnz(x, y) => vec![x, y],
+        };
+        operands.into_iter()
+                .filter_map(|operand| match operand {
+                    Operand::Reg(reg) => Some(reg),
+                    Operand::Const(_) => None,
+                })
+                .collect()
+    }
+}
+
+/// Decodes every non-empty line of `text`, reporting all problems as one parse error.
+pub fn decode_program(text: &str) -> Result<Vec<Instruction>, Error> {
+    Ok(assemble(text)?)
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operand::Reg(reg) => write!(f, "{}", register_name(reg)),
+            Operand::Const(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Snd(x) => write!(f, "snd {}", x),
+            Instruction::Set(r, y) => write!(f, "set {} {}", register_name(r), y),
+            Instruction::Add(r, y) => write!(f, "add {} {}", register_name(r), y),
+            Instruction::Sub(r, y) => write!(f, "sub {} {}", register_name(r), y),
+            Instruction::Mul(r, y) => write!(f, "mul {} {}", register_name(r), y),
+            Instruction::Mod(r, y) => write!(f, "mod {} {}", register_name(r), y),
+            Instruction::Rcv(r) => write!(f, "rcv {}", register_name(r)),
+            Instruction::Jgz(x, y) => write!(f, "jgz {} {}", x, y),
+            Instruction::Jnz(x, y) => write!(f, "jnz {} {}", x, y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Instruction::decode("set a 1").unwrap(), Instruction::Set(0, Operand::Const(1)));
+        assert_eq!(Instruction::decode("jgz 1 -p").unwrap_err().to_string(),
+                   "parse error at 1:7: invalid operand `-p` (hint: operands are lowercase register names or integer constants)");
+        assert_eq!(Instruction::decode("add 3 b").unwrap_err().to_string(),
+                   "parse error at 1:5: register required `3` (hint: `add` writes to its first operand, use a register name)");
+        assert_eq!(Instruction::decode("rcv a b").unwrap_err().to_string(),
+                   "parse error at 1:7: wrong operand count, expected 1 found 2 `b` (hint: usage: rcv X)");
+
+        let program = decode_program("snd a\n\n  jnz 1 b\n").unwrap();
+        assert_eq!(program, vec![Instruction::Snd(Operand::Reg(0)), Instruction::Jnz(Operand::Const(1), Operand::Reg(1))]);
+        assert_eq!(program[1].to_string(), "jnz 1 b");
+        assert_eq!(program[1].reads(), vec![1]);
+        assert_eq!(Instruction::decode("mul a b").unwrap().reads(), vec![0, 1]);
+        assert_eq!(Instruction::decode("rcv c").unwrap().writes(), Some(2));
+        assert_eq!(Instruction::decode("add count q").unwrap().to_string(), "add count q");
+        assert_eq!((named_register("aa"), named_register("ba"), named_register("Ab")), (Some(26), Some(52), None));
+        assert_eq!(register_name(named_register("zzzzzzzzzzzz").unwrap()), "zzzzzzzzzzzz");
+        assert_eq!(named_register("abcdefghijklm"), None);
+        assert_eq!(decode_program("snd a\n\nnop").unwrap_err().to_string(),
+                   "parse error at 3:1: unknown opcode `nop` (hint: expected one of snd, set, add, sub, mul, mod, rcv, jgz, jnz)");
+    }
+}
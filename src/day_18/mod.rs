@@ -0,0 +1,71 @@
+use error::Error;
+use solution::Solution;
+
+mod arithmetic;
+mod assembler;
+mod debugger;
+mod decompiler;
+mod diagnostic;
+mod dialect;
+mod instruction;
+mod network;
+mod optimizer;
+mod profile;
+mod registers;
+mod snapshot;
+mod threaded;
+mod trace;
+mod transpiler;
+
+pub use self::arithmetic::{Arithmetic, Fault, FaultKind, Modulo, Overflow};
+pub use self::assembler::Assembler;
+pub use self::debugger::{Breakpoint, Comparison, Debugger};
+pub use self::decompiler::{BasicBlock, ControlFlowGraph, decompile};
+pub use self::diagnostic::{Diagnostic, Diagnostics, assemble, assemble_dialect, source_lines, validate};
+pub use self::dialect::Dialect;
+pub use self::instruction::{Instruction, Operand, Register, decode_program, named_register, register, register_name};
+pub use self::network::{Network, NetworkReport, Outcome, ProgramReport, State, Topology};
+pub use self::optimizer::{Fused, optimize};
+pub use self::profile::{HotLoop, Profile};
+pub use self::registers::Registers;
+pub use self::snapshot::Snapshot;
+pub use self::trace::{End, Frame, Replay, Step, Trace};
+pub use self::transpiler::transpile;
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    type Input = Vec<Instruction>;
+
+    fn day(&self) -> u32 {
+        18
+    }
+
+    fn name(&self) -> &'static str {
+        "Duet"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "assembly.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<Instruction>, Error> {
+        Ok(assemble_dialect(input, Dialect::Duet)?)
+    }
+
+    fn first_puzzle(&self, program: &Vec<Instruction>) -> Result<String, Error> {
+        let mut assembler = Assembler::with_program(0, Dialect::Sound, program)?;
+        while !assembler.terminated() {
+            if let Some(freq) = assembler.execute_next_instruction()? {
+                return Ok(format!("{}", freq));
+            }
+        }
+
+        Err(Error::no_solution("terminated without recovering a frequency"))
+    }
+
+    fn second_puzzle(&self, program: &Vec<Instruction>) -> Result<String, Error> {
+        let report = Network::new(program, 2, Topology::Pairwise)?.run(u64::MAX)?;
+        Ok(format!("{}", report.programs[1].sent))
+    }
+}
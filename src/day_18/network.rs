@@ -0,0 +1,307 @@
+use std::fmt;
+use std::mem;
+use error::Error;
+use super::assembler::Assembler;
+use super::dialect::Dialect;
+use super::instruction::{Instruction, register};
+use super::registers::Registers;
+use super::threaded;
+
+/// Which programs receive the values a program sends.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Topology {
+    /// Programs `2k` and `2k + 1` send to each other, a program left without a partner sends to nobody.
+    Pairwise,
+    /// Program `i` sends to program `i + 1`, the last one to the first.
+    Ring,
+    /// Every program sends to all the others.
+    Broadcast,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum State {
+    Running,
+    Waiting,
+    Terminated,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// Every program terminated.
+    Halted,
+    /// Every program is waiting on `rcv` or terminated, with at least one waiting.
+    Deadlock,
+    /// The step limit was reached while some program could still run.
+    StepLimit,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProgramReport {
+    pub state: State,
+    pub ic: i64,
+    /// Source line of the instruction at `ic`, `None` once the program terminated.
+    pub line: Option<usize>,
+    pub sent: u64,
+    pub received: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct NetworkReport {
+    pub outcome: Outcome,
+    pub steps: u64,
+    pub programs: Vec<ProgramReport>,
+}
+
+/// Runs several `Assembler`s round-robin, one instruction each, delivering sent values over `topology`.
+pub struct Network {
+    pub programs: Vec<Assembler>,
+    topology: Topology,
+    sent: Vec<u64>,
+    received: Vec<u64>,
+}
+
+impl Topology {
+    pub fn parse(name: &str) -> Option<Topology> {
+        match name {
+            "pairwise" => Some(Topology::Pairwise),
+            "ring" => Some(Topology::Ring),
+            "broadcast" => Some(Topology::Broadcast),
+            _ => None,
+        }
+    }
+
+    /// Programs receiving what program `id` of `count` sends.
+    pub fn destinations(&self, id: usize, count: usize) -> Vec<usize> {
+        match *self {
+            Topology::Pairwise => Some(id ^ 1).filter(|&peer| peer < count).into_iter().collect(),
+            Topology::Ring => vec![(id + 1) % count],
+            Topology::Broadcast => (0 .. count).filter(|&peer| peer != id).collect(),
+        }
+    }
+}
+
+impl Network {
+    /// `count` copies of the Duet `program`, each with its index in register `p`.
+    pub fn new(program: &[Instruction], count: usize, topology: Topology) -> Result<Network, Error> {
+        Network::with_initial(program, count, topology, &Registers::new())
+    }
+
+    /// `new` with every program starting from the `initial` registers, `p` still holding its index.
+    pub fn with_initial(program: &[Instruction], count: usize, topology: Topology, initial: &Registers) -> Result<Network, Error> {
+        let programs = (0 .. count).map(|id| {
+                                       let mut registers = initial.clone();
+                                       registers[register('p').unwrap_or(0)] = id as i64;
+                                       Assembler::with_initial(registers, Dialect::Duet, program)
+                                   })
+                                   .collect::<Result<_, _>>()?;
+        Ok(Network::with_programs(programs, topology))
+    }
+
+    pub fn with_programs(programs: Vec<Assembler>, topology: Topology) -> Network {
+        let count = programs.len();
+        Network {
+            programs,
+            topology,
+            sent: vec![0; count],
+            received: vec![0; count],
+        }
+    }
+
+    /// Numbers the instructions of every program by the source lines they were assembled from, see `source_lines`.
+    pub fn set_lines(&mut self, lines: &[usize]) {
+        for program in &mut self.programs {
+            program.set_lines(lines);
+        }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn state(&self, id: usize) -> State {
+        let program = &self.programs[id];
+        if program.terminated() {
+            State::Terminated
+        }
+        else if program.waiting_message() {
+            State::Waiting
+        }
+        else {
+            State::Running
+        }
+    }
+
+    /// Executes one instruction of program `id` and delivers what it sends, returns false if it is blocked.
+    pub fn step(&mut self, id: usize) -> Result<bool, Error> {
+        if self.state(id) != State::Running {
+            return Ok(false);
+        }
+
+        if let Some(Instruction::Rcv(_)) = self.programs[id].current() {
+            self.received[id] += 1;
+        }
+        if let Some(value) = self.programs[id].execute_next_instruction()? {
+            self.sent[id] += 1;
+            for peer in self.topology.destinations(id, self.programs.len()) {
+                self.programs[peer].push_message(value);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Runs until every program is blocked or terminated, or `limit` instructions were executed.
+    pub fn run(&mut self, limit: u64) -> Result<NetworkReport, Error> {
+        let mut steps = 0;
+        let outcome = loop {
+            let mut progressed = false;
+            for id in 0 .. self.programs.len() {
+                if steps == limit {
+                    break;
+                }
+                if self.step(id)? {
+                    progressed = true;
+                    steps += 1;
+                }
+            }
+
+            if !progressed {
+                let halted = (0 .. self.programs.len()).all(|id| self.state(id) == State::Terminated);
+                break if halted { Outcome::Halted } else { Outcome::Deadlock };
+            }
+            if steps == limit {
+                break Outcome::StepLimit;
+            }
+        };
+
+        Ok(NetworkReport { outcome, steps, programs: self.reports() })
+    }
+
+    /// Runs every program on its own thread, exchanging values over channels, until `limit` instructions were executed.
+    /// Only `Pairwise` and `Ring` are accepted: each program receives from a single peer, so the result matches `run`.
+    /// Under `Broadcast` the order values arrive in depends on thread timing.
+    pub fn run_threaded(&mut self, limit: u64) -> Result<NetworkReport, Error> {
+        if self.topology == Topology::Broadcast {
+            return Err(Error::invalid_state("broadcast networks cannot run on threads, their delivery order is not deterministic"));
+        }
+        let programs = mem::take(&mut self.programs);
+        let (outcome, finished) = threaded::run(programs, self.topology, limit)?;
+
+        let mut steps = 0;
+        for (id, program) in finished.into_iter().enumerate() {
+            self.sent[id] += program.sent;
+            self.received[id] += program.received;
+            steps += program.steps;
+            self.programs.push(program.assembler);
+        }
+        Ok(NetworkReport { outcome, steps, programs: self.reports() })
+    }
+
+    pub fn reports(&self) -> Vec<ProgramReport> {
+        (0 .. self.programs.len()).map(|id| ProgramReport {
+                                      state: self.state(id),
+                                      ic: self.programs[id].ic,
+                                      line: self.programs[id].line(self.programs[id].ic),
+                                      sent: self.sent[id],
+                                      received: self.received[id],
+                                  })
+                                  .collect()
+    }
+}
+
+impl fmt::Display for NetworkReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let outcome = match self.outcome {
+            Outcome::Halted => "halted",
+            Outcome::Deadlock => "deadlock",
+            Outcome::StepLimit => "step limit reached",
+        };
+        write!(f, "{} after {} steps", outcome, self.steps)?;
+        for (id, program) in self.programs.iter().enumerate() {
+            let line = program.line.map_or_else(|| format!("ic {}", program.ic), |line| format!("line {}", line));
+            let state = match program.state {
+                State::Running => format!("running at {}", line),
+                State::Waiting => format!("waiting at {}", line),
+                State::Terminated => String::from("terminated"),
+            };
+            write!(f, "\nprogram {}: {}, sent {}, received {}", id, state, program.sent, program.received)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{assemble, source_lines};
+
+    #[test]
+    fn test_network() {
+        let program = assemble("snd 1\nsnd 2\nsnd p\nrcv a\nrcv b\nrcv c\nrcv d").unwrap();
+
+        let report = Network::new(&program, 2, Topology::Pairwise).unwrap().run(u64::MAX).unwrap();
+        assert_eq!(report.outcome, Outcome::Deadlock);
+        assert_eq!(report.programs.iter().map(|program| (program.sent, program.received)).collect::<Vec<_>>(), vec![(3, 3), (3, 3)]);
+        assert_eq!(report.to_string(), "deadlock after 12 steps\n\
+                                         program 0: waiting at line 7, sent 3, received 3\n\
+                                         program 1: waiting at line 7, sent 3, received 3");
+
+        let mut spaced = Network::new(&program, 2, Topology::Pairwise).unwrap();
+        spaced.set_lines(&source_lines("snd 1\nsnd 2\nsnd p\n\nrcv a\nrcv b\nrcv c\n\nrcv d"));
+        assert!(spaced.run(u64::MAX).unwrap().to_string().ends_with("program 1: waiting at line 9, sent 3, received 3"));
+
+        let mut ring = Network::new(&program, 3, Topology::Ring).unwrap();
+        let report = ring.run(u64::MAX).unwrap();
+        assert_eq!(report.outcome, Outcome::Deadlock);
+        assert_eq!(ring.programs[0].register('c'), 2);
+        assert_eq!(ring.programs[1].register('c'), 0);
+
+        let report = Network::new(&program, 3, Topology::Broadcast).unwrap().run(u64::MAX).unwrap();
+        assert_eq!(report.outcome, Outcome::Halted);
+        assert!(report.programs.iter().all(|program| program.state == State::Terminated && program.received == 4));
+
+        let initial = [(super::super::named_register("d").unwrap(), 9), (super::super::named_register("p").unwrap(), 5)].iter().cloned().collect();
+        let mut seeded = Network::with_initial(&program, 2, Topology::Pairwise, &initial).unwrap();
+        seeded.run(u64::MAX).unwrap();
+        assert_eq!((seeded.programs[1].register('p'), seeded.programs[1].register('c'), seeded.programs[1].register('d')), (1, 0, 9));
+
+        let report = Network::new(&program, 3, Topology::Pairwise).unwrap().run(5).unwrap();
+        assert_eq!((report.outcome, report.steps), (Outcome::StepLimit, 5));
+        assert_eq!(Topology::Pairwise.destinations(2, 3), vec![]);
+    }
+
+    #[test]
+    fn test_run_threaded() {
+        let token = assemble("jgz p 2\nsnd 10\nrcv a\nadd a -1\nsnd a\njgz a -3").unwrap();
+        let sample = assemble("snd 1\nsnd 2\nsnd p\nrcv a\nrcv b\nrcv c\nrcv d").unwrap();
+        for &(program, count, topology) in &[(&token, 4, Topology::Ring),
+                                             (&token, 2, Topology::Pairwise),
+                                             (&sample, 2, Topology::Pairwise),
+                                             (&sample, 3, Topology::Ring),
+                                             (&sample, 1, Topology::Ring)] {
+            for _ in 0 .. 20 {
+                let mut cooperative = Network::new(program, count, topology).unwrap();
+                let mut threaded = Network::new(program, count, topology).unwrap();
+                let expected = cooperative.run(u64::MAX).unwrap();
+                let report = threaded.run_threaded(u64::MAX).unwrap();
+                assert_eq!((report.outcome, &report.programs), (expected.outcome, &expected.programs));
+                assert_eq!(report.steps, expected.steps);
+                for (lhs, rhs) in threaded.programs.iter().zip(&cooperative.programs) {
+                    assert_eq!(lhs.registers, rhs.registers);
+                }
+            }
+        }
+
+        let faulty = assemble("snd 1\nrcv a\nmod a 0").unwrap();
+        let error = Network::new(&faulty, 2, Topology::Pairwise).unwrap().run_threaded(u64::MAX).unwrap_err().to_string();
+        assert!(error.ends_with(": fault: ic 2: mod by zero: mod a 0"));
+
+        let mut broadcast = Network::new(&sample, 3, Topology::Broadcast).unwrap();
+        assert_eq!(broadcast.run_threaded(u64::MAX).unwrap_err().to_string(),
+                   "invalid state: broadcast networks cannot run on threads, their delivery order is not deterministic");
+        assert_eq!(broadcast.programs.len(), 3);
+
+        let spin = assemble("add a 1\njgz 1 -1").unwrap();
+        let report = Network::new(&spin, 2, Topology::Ring).unwrap().run_threaded(1000).unwrap();
+        assert_eq!((report.outcome, report.steps), (Outcome::StepLimit, 1000));
+    }
+}
@@ -0,0 +1,192 @@
+use super::instruction::{Instruction, Operand, Register};
+
+/// Synthetic instruction replacing a whole loop, `end` is the index of the jump closing it.
+/// It only applies when its guard holds, otherwise the original instructions run.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fused {
+    /// Loop adding `sign * step` to `target` while counting `counter` down to 0.
+    /// Runs as `target += sign * step * counter; counter = 0` when `counter >= 1`.
+    Increment { target: Register, step: Operand, sign: i64, counter: Register, end: usize },
+    /// Increment loop restarted with `counter = source` until `outer` counts down to 0.
+    /// Runs as `target += sign * step * source * outer; counter = 0; outer = 0` when `source >= 1` and `outer >= 1`.
+    Multiply { target: Register, step: Operand, sign: i64, source: Operand, counter: Register, outer: Register, end: usize },
+    /// Loop clearing `flag` if `factor * index == value` for some `index` counting up to `value`.
+    /// Runs as a divisibility test followed by `index = value; scratch = 0` when `index < value`.
+    Divisible { flag: Register, factor: Register, index: Register, value: Register, scratch: Register, end: usize },
+}
+
+/// Register counted down by one.
+fn decrement(instruction: &Instruction) -> Option<Register> {
+    match *instruction {
+        Instruction::Add(r, Operand::Const(-1)) | Instruction::Sub(r, Operand::Const(1)) => Some(r),
+        _ => None,
+    }
+}
+
+/// Register changed by `sign * step`.
+fn accumulate(instruction: &Instruction) -> Option<(Register, Operand, i64)> {
+    match *instruction {
+        Instruction::Add(r, y) => Some((r, y, 1)),
+        Instruction::Sub(r, Operand::Const(k)) => k.checked_neg().map(|k| (r, Operand::Const(k), 1)),
+        Instruction::Sub(r, y) => Some((r, y, -1)),
+        _ => None,
+    }
+}
+
+/// Register tested by a jump back by `offset`.
+fn back_jump(instruction: &Instruction, offset: i64) -> Option<Register> {
+    match *instruction {
+        Instruction::Jnz(Operand::Reg(r), Operand::Const(o)) | Instruction::Jgz(Operand::Reg(r), Operand::Const(o)) if o == -offset => Some(r),
+        _ => None,
+    }
+}
+
+fn increment(program: &[Instruction], at: usize) -> Option<Fused> {
+    let body = program.get(at .. at + 3)?;
+    let counter = back_jump(&body[2], 2)?;
+    let (target, step, sign) = match (decrement(&body[0]), decrement(&body[1])) {
+        (Some(c), _) if c == counter => accumulate(&body[1])?,
+        (_, Some(c)) if c == counter => accumulate(&body[0])?,
+        _ => return None,
+    };
+    if target == counter || step == Operand::Reg(target) || step == Operand::Reg(counter) {
+        return None;
+    }
+    Some(Fused::Increment { target, step, sign, counter, end: at + 2 })
+}
+
+fn multiply(program: &[Instruction], at: usize) -> Option<Fused> {
+    let body = program.get(at .. at + 6)?;
+    let (counter, source) = match body[0] {
+        Instruction::Set(c, source) => (c, source),
+        _ => return None,
+    };
+    let (target, step, sign) = match increment(program, at + 1)? {
+        Fused::Increment { target, step, sign, counter: c, .. } if c == counter => (target, step, sign),
+        _ => return None,
+    };
+    let outer = decrement(&body[4])?;
+    if back_jump(&body[5], 5)? != outer || outer == target || outer == counter {
+        return None;
+    }
+    let touched = [Operand::Reg(target), Operand::Reg(counter), Operand::Reg(outer)];
+    if touched.contains(&source) || step == Operand::Reg(outer) {
+        return None;
+    }
+    Some(Fused::Multiply { target, step, sign, source, counter, outer, end: at + 5 })
+}
+
+fn divisible(program: &[Instruction], at: usize) -> Option<Fused> {
+    let body = program.get(at .. at + 9)?;
+    let (scratch, factor) = match body[0] {
+        Instruction::Set(g, Operand::Reg(d)) => (g, d),
+        _ => return None,
+    };
+    let (index, value, flag) = match (body[1], body[2], body[4]) {
+        (Instruction::Mul(g1, Operand::Reg(e)), Instruction::Sub(g2, Operand::Reg(b)), Instruction::Set(f, Operand::Const(0)))
+            if g1 == scratch && g2 == scratch => (e, b, f),
+        _ => return None,
+    };
+    let expected = [
+        Instruction::Jnz(Operand::Reg(scratch), Operand::Const(2)),
+        Instruction::Sub(index, Operand::Const(-1)),
+        Instruction::Set(scratch, Operand::Reg(index)),
+        Instruction::Sub(scratch, Operand::Reg(value)),
+        Instruction::Jnz(Operand::Reg(scratch), Operand::Const(-8)),
+    ];
+    if body[3] != expected[0] || body[5 ..] != expected[1 ..] {
+        return None;
+    }
+
+    let mut registers = vec![flag, factor, index, value, scratch];
+    registers.sort();
+    registers.dedup();
+    if registers.len() != 5 {
+        return None;
+    }
+    Some(Fused::Divisible { flag, factor, index, value, scratch, end: at + 8 })
+}
+
+/// Fused instruction for every loop of `program` matching a known idiom, indexed by the loop's first instruction.
+/// Nothing jumps into the middle of a fused loop in the idioms recognized, so the layout of `program` is kept.
+pub fn optimize(program: &[Instruction]) -> Vec<Option<Fused>> {
+    (0 .. program.len()).map(|at| {
+                            multiply(program, at).or_else(|| divisible(program, at))
+                                                 .or_else(|| increment(program, at))
+                                                 .filter(|fused| !jumps_into(program, at, fused.end()))
+                        })
+                        .collect()
+}
+
+/// Some jump outside `start ..= end` lands strictly inside it.
+fn jumps_into(program: &[Instruction], start: usize, end: usize) -> bool {
+    program.iter().enumerate().any(|(ic, instruction)| {
+        let offset = match *instruction {
+            Instruction::Jnz(_, offset) | Instruction::Jgz(_, offset) => offset,
+            _ => return false,
+        };
+        match offset {
+            Operand::Const(offset) => {
+                let target = ic as i64 + offset;
+                (ic < start || ic > end) && target > start as i64 && target <= end as i64
+            },
+            Operand::Reg(_) => true,
+        }
+    })
+}
+
+impl Fused {
+    pub fn end(&self) -> usize {
+        match *self {
+            Fused::Increment { end, .. } | Fused::Multiply { end, .. } | Fused::Divisible { end, .. } => end,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Assembler, Dialect, assemble};
+
+    fn run(text: &str, dialect: Dialect, optimized: bool, setup: &[(char, i64)]) -> (Assembler, u64) {
+        let mut assembler = Assembler::with_program(0, dialect, &assemble(text).unwrap()).unwrap();
+        for &(name, value) in setup {
+            assembler.registers[super::super::register(name).unwrap()] = value;
+        }
+        if optimized {
+            assembler.optimize();
+        }
+        let steps = assembler.run(u64::MAX).unwrap();
+        (assembler, steps)
+    }
+
+    #[test]
+    fn test_optimize() {
+        let multiply = "set a 0\nset c b\nadd a 3\nadd c -1\njgz c -2\nadd d -1\njgz d -5\nset e a";
+        let program = assemble(multiply).unwrap();
+        let fused = optimize(&program);
+        assert_eq!(fused[1], Some(Fused::Multiply { target: 0, step: Operand::Const(3), sign: 1, source: Operand::Reg(1),
+                                                    counter: 2, outer: 3, end: 6 }));
+        assert_eq!(fused[2], Some(Fused::Increment { target: 0, step: Operand::Const(3), sign: 1, counter: 2, end: 4 }));
+        assert_eq!(fused.iter().filter(|fused| fused.is_some()).count(), 2);
+
+        for setup in &[[('b', 7), ('d', 5)], [('b', 0), ('d', 3)], [('b', 4), ('d', -2)]] {
+            let (naive, _) = run(multiply, Dialect::Duet, false, setup);
+            let (fast, steps) = run(multiply, Dialect::Duet, true, setup);
+            assert_eq!(naive.registers, fast.registers);
+            assert!(setup[0].1 < 1 || setup[1].1 < 1 || steps == 3);
+        }
+
+        let divisible = "set f 1\nset e 2\nset g d\nmul g e\nsub g b\njnz g 2\nset f 0\nsub e -1\nset g e\nsub g b\njnz g -8\nset h f";
+        assert!(optimize(&assemble(divisible).unwrap())[2].is_some());
+        for setup in &[[('b', 91), ('d', 7)], [('b', 97), ('d', 7)], [('b', 14), ('d', 7)], [('b', 12), ('d', 0)]] {
+            let (naive, _) = run(divisible, Dialect::Coprocessor, false, setup);
+            let (fast, steps) = run(divisible, Dialect::Coprocessor, true, setup);
+            assert_eq!(naive.registers, fast.registers);
+            assert_eq!(steps, 4);
+        }
+
+        let entered = assemble("jgz a 3\nadd a 3\nadd c -1\njgz c -2").unwrap();
+        assert!(optimize(&entered).iter().all(|fused| fused.is_none()));
+    }
+}
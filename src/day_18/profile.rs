@@ -0,0 +1,138 @@
+use std::collections::{BTreeSet, HashMap};
+use super::instruction::{Instruction, Register, register_name};
+
+/// Execution and jump counts gathered while an `Assembler` runs.
+#[derive(Clone, Default, Debug)]
+pub struct Profile {
+    counts: Vec<u64>,
+    edges: HashMap<(usize, i64), u64>,
+}
+
+/// Body of a backward jump, `start` and `end` are instruction indices.
+#[derive(Clone, PartialEq, Debug)]
+pub struct HotLoop {
+    pub start: usize,
+    pub end: usize,
+    pub iterations: u64,
+    pub executed: u64,
+    pub reads: Vec<Register>,
+    pub writes: Vec<Register>,
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    pub fn record(&mut self, ic: usize) {
+        if ic >= self.counts.len() {
+            self.counts.resize(ic + 1, 0);
+        }
+        self.counts[ic] += 1;
+    }
+
+    /// Records a taken jump, `to` is outside the program when the jump terminates it.
+    pub fn record_jump(&mut self, from: usize, to: i64) {
+        *self.edges.entry((from, to)).or_insert(0) += 1;
+    }
+
+    pub fn count(&self, ic: usize) -> u64 {
+        self.counts.get(ic).cloned().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Taken jumps as `(from, to, count)`, most frequent first.
+    pub fn edges(&self) -> Vec<(usize, i64, u64)> {
+        let mut edges = self.edges.iter().map(|(&(from, to), &count)| (from, to, count)).collect::<Vec<_>>();
+        edges.sort_by(|a, b| b.2.cmp(&a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+        edges
+    }
+
+    /// Loops formed by backward jumps, the ones executing the most instructions first.
+    pub fn hot_loops(&self, program: &[Instruction]) -> Vec<HotLoop> {
+        let mut loops = self.edges()
+                            .into_iter()
+                            .filter(|&(from, to, _)| to >= 0 && to as usize <= from)
+                            .map(|(end, start, iterations)| {
+                                let start = start as usize;
+                                let body = &program[start ..= end.min(program.len() - 1)];
+                                let mut reads = BTreeSet::new();
+                                let mut writes = BTreeSet::new();
+                                for instruction in body {
+                                    reads.extend(instruction.reads());
+                                    writes.extend(instruction.writes());
+                                }
+                                HotLoop {
+                                    start,
+                                    end,
+                                    iterations,
+                                    executed: (start ..= end).map(|ic| self.count(ic)).sum(),
+                                    reads: reads.into_iter().collect(),
+                                    writes: writes.into_iter().collect(),
+                                }
+                            })
+                            .collect::<Vec<_>>();
+        loops.sort_by(|a, b| b.executed.cmp(&a.executed).then(a.start.cmp(&b.start)));
+        loops
+    }
+
+    /// Annotated listing of `program` followed by the jumps and the `top` hottest loops.
+    pub fn report(&self, program: &[Instruction], top: usize) -> String {
+        let total = self.total();
+        let share = |count: u64| if total == 0 { 0.0 } else { count as f64 * 100.0 / total as f64 };
+        let names = |regs: &[Register]| regs.iter().map(|&reg| register_name(reg)).collect::<Vec<_>>().join(" ");
+
+        let mut lines = vec![format!("executed {} instructions", total),
+                             format!("{:>12} {:>7} {:>5}  instruction", "count", "share", "line")];
+        for (ic, instruction) in program.iter().enumerate() {
+            let count = self.count(ic);
+            lines.push(format!("{:>12} {:>6.2}% {:>5}  {}", count, share(count), ic + 1, instruction));
+        }
+
+        lines.push(String::from("jumps:"));
+        for (from, to, count) in self.edges() {
+            lines.push(format!("{:>12}  line {} -> line {}", count, from + 1, to + 1));
+        }
+
+        lines.push(String::from("hot loops:"));
+        for hot in self.hot_loops(program).iter().take(top) {
+            lines.push(format!("  lines {}-{}: {} iterations, {} instructions ({:.2}%), reads {}, writes {}",
+                               hot.start + 1, hot.end + 1, hot.iterations, hot.executed, share(hot.executed),
+                               names(&hot.reads), names(&hot.writes)));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Assembler, Dialect, assemble};
+
+    #[test]
+    fn test_profile() {
+        let program = assemble("set a 3\nset b 2\nsub b 1\njnz b -1\nsub a 1\njnz a -4\nset c a").unwrap();
+        let mut assembler = Assembler::with_program(0, Dialect::Coprocessor, &program).unwrap();
+        assembler.enable_profile();
+        assert_eq!(assembler.run(1000).unwrap(), 23);
+
+        let profile = assembler.profile().unwrap();
+        assert_eq!(profile.count(2), 6);
+        assert_eq!(profile.count(5), 3);
+        assert_eq!(profile.total(), 23);
+        assert_eq!(profile.edges(), vec![(3, 2, 3), (5, 1, 2)]);
+
+        let loops = profile.hot_loops(&program);
+        assert_eq!(loops.len(), 2);
+        assert_eq!((loops[0].start, loops[0].end, loops[0].executed), (1, 5, 21));
+        assert_eq!((loops[1].start, loops[1].end, loops[1].iterations), (2, 3, 3));
+        assert_eq!(loops[1].reads, vec![1]);
+        assert_eq!(loops[0].writes, vec![0, 1]);
+
+        let report = profile.report(&program, 1);
+        assert!(report.contains("           6  26.09%     3  sub b 1"));
+        assert!(report.ends_with("hot loops:\n  lines 2-6: 2 iterations, 21 instructions (91.30%), reads a b, writes a b"));
+    }
+}
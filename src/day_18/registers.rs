@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Index, IndexMut};
+use super::instruction::{LETTERS, Register, register_name};
+
+/// Values of every register, 0 until written.
+/// `a` to `z` are kept in an array, registers with longer names as they get written.
+#[derive(Clone, Default, Debug)]
+pub struct Registers {
+    letters: [i64; LETTERS],
+    named: BTreeMap<Register, i64>,
+}
+
+impl Registers {
+    pub fn new() -> Registers {
+        Registers::default()
+    }
+
+    /// Registers holding a non-zero value, in index order.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (Register, i64)> + 'a {
+        self.letters.iter().cloned().enumerate()
+                   .chain(self.named.iter().map(|(&reg, &value)| (reg, value)))
+                   .filter(|&(_, value)| value != 0)
+    }
+
+    /// Registers differing from `other`, as `(register, value here, value in other)`.
+    pub fn diff(&self, other: &Registers) -> Vec<(Register, i64, i64)> {
+        let mut regs = self.iter().chain(other.iter()).map(|(reg, _)| reg).collect::<Vec<_>>();
+        regs.sort();
+        regs.dedup();
+        regs.into_iter()
+            .filter(|&reg| self[reg] != other[reg])
+            .map(|reg| (reg, self[reg], other[reg]))
+            .collect()
+    }
+}
+
+impl Index<Register> for Registers {
+    type Output = i64;
+
+    fn index(&self, reg: Register) -> &i64 {
+        if reg < LETTERS {
+            &self.letters[reg]
+        }
+        else {
+            self.named.get(&reg).unwrap_or(&0)
+        }
+    }
+}
+
+impl IndexMut<Register> for Registers {
+    fn index_mut(&mut self, reg: Register) -> &mut i64 {
+        if reg < LETTERS {
+            &mut self.letters[reg]
+        }
+        else {
+            self.named.entry(reg).or_insert(0)
+        }
+    }
+}
+
+/// Registers are equal when every register holds the same value, whether it was written or not.
+impl PartialEq for Registers {
+    fn eq(&self, other: &Registers) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Registers {}
+
+impl FromIterator<(Register, i64)> for Registers {
+    fn from_iter<I: IntoIterator<Item = (Register, i64)>>(values: I) -> Registers {
+        let mut registers = Registers::new();
+        for (reg, value) in values {
+            registers[reg] = value;
+        }
+        registers
+    }
+}
+
+/// Non-zero registers as `name=value`, separated by spaces.
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values = self.iter().map(|(reg, value)| format!("{}={}", register_name(reg), value)).collect::<Vec<_>>();
+        write!(f, "{}", values.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::instruction::named_register;
+
+    #[test]
+    fn test_registers() {
+        let count = named_register("count").unwrap();
+        let mut registers = [(0, 1), (count, 5), (16, 7)].iter().cloned().collect::<Registers>();
+        assert_eq!(registers.to_string(), "a=1 q=7 count=5");
+        assert_eq!(registers[named_register("other").unwrap()], 0);
+
+        let mut other = registers.clone();
+        other[count] = 0;
+        other[named_register("z").unwrap()] = 2;
+        assert_eq!(registers.diff(&other), vec![(25, 0, 2), (count, 5, 0)]);
+        registers[count] = 0;
+        registers[25] = 2;
+        assert_eq!(registers, other);
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use error::Error;
+use super::arithmetic::{Arithmetic, Modulo, Overflow};
+use super::dialect::Dialect;
+use super::instruction::{Instruction, named_register, register_name};
+use super::registers::Registers;
+
+/// Full machine state of an `Assembler`, without its profile, trace or fused instructions.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    pub dialect: Dialect,
+    pub arithmetic: Arithmetic,
+    pub registers: Registers,
+    pub instructions: Vec<Instruction>,
+    /// Source line of every instruction.
+    pub lines: Vec<usize>,
+    pub messages: VecDeque<i64>,
+    pub last_sound: Option<i64>,
+    pub ic: i64,
+}
+
+impl Snapshot {
+    /// Writes one `key values...` line per field, then the program, one instruction per line:
+    ///
+    /// ```text
+    /// snapshot 1
+    /// dialect duet
+    /// arithmetic checked remainder
+    /// ic 3
+    /// registers a=2 p=1
+    /// sound 5
+    /// messages 4 8
+    /// lines 1 3
+    /// program 2
+    /// snd a
+    /// rcv b
+    /// ```
+    ///
+    /// Zero registers are left out, and so is `sound` when nothing was played.
+    /// `lines` is left out when the instructions are on lines 1, 2, 3 and so on, which is also how a missing one reads.
+    /// A missing `arithmetic` line reads as the default policy.
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        writeln!(output, "snapshot 1")?;
+        writeln!(output, "dialect {}", self.dialect)?;
+        writeln!(output, "arithmetic {} {}", self.arithmetic.overflow, self.arithmetic.modulo)?;
+        writeln!(output, "ic {}", self.ic)?;
+        write!(output, "registers")?;
+        for (reg, value) in self.registers.iter() {
+            write!(output, " {}={}", register_name(reg), value)?;
+        }
+        writeln!(output)?;
+        if let Some(sound) = self.last_sound {
+            writeln!(output, "sound {}", sound)?;
+        }
+        write!(output, "messages")?;
+        for message in &self.messages {
+            write!(output, " {}", message)?;
+        }
+        writeln!(output)?;
+        if !self.lines.iter().enumerate().all(|(ic, &line)| line == ic + 1) {
+            write!(output, "lines")?;
+            for line in &self.lines {
+                write!(output, " {}", line)?;
+            }
+            writeln!(output)?;
+        }
+        writeln!(output, "program {}", self.instructions.len())?;
+        for instruction in &self.instructions {
+            writeln!(output, "{}", instruction)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(input: R) -> Result<Snapshot, Error> {
+        let mut snapshot = Snapshot {
+            dialect: Dialect::Duet,
+            arithmetic: Arithmetic::default(),
+            registers: Registers::new(),
+            instructions: Vec::new(),
+            lines: Vec::new(),
+            messages: VecDeque::new(),
+            last_sound: None,
+            ic: 0,
+        };
+        let mut dialect = None;
+        let mut length = None;
+
+        for (idx, line) in input.lines().enumerate() {
+            let line = line?;
+            let line_no = idx + 1;
+            if length.is_some() {
+                snapshot.instructions.push(Instruction::decode(&line).map_err(|error| error.on_line(line_no))?);
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+            let key = tokens.next().unwrap_or("");
+            let values = tokens.collect::<Vec<_>>();
+            let number = |token: &str| token.parse::<i64>().map_err(|_| Error::at_token(line_no, &line, token, format!("invalid number: {}", token)));
+            let single = || match values.as_slice() {
+                &[value] => Ok(value),
+                _ => Err(Error::parse(line_no, 1, format!("expected a single value after {}", key))),
+            };
+
+            match (line_no, key) {
+                (1, "snapshot") if values == ["1"] => (),
+                (1, _) => return Err(Error::parse(1, 1, "expected header: snapshot 1")),
+                (_, "dialect") => {
+                    let name = single()?;
+                    dialect = Some(Dialect::parse(name).ok_or_else(|| Error::at_token(line_no, &line, name, format!("unknown dialect: {}", name)))?);
+                },
+                (_, "arithmetic") => match values.as_slice() {
+                    &[overflow, modulo] => {
+                        snapshot.arithmetic.overflow = Overflow::parse(overflow).ok_or_else(|| Error::at_token(line_no, &line, overflow, format!("unknown overflow policy: {}", overflow)))?;
+                        snapshot.arithmetic.modulo = Modulo::parse(modulo).ok_or_else(|| Error::at_token(line_no, &line, modulo, format!("unknown modulo: {}", modulo)))?;
+                    },
+                    _ => return Err(Error::parse(line_no, 1, "expected an overflow policy and a modulo after arithmetic")),
+                },
+                (_, "ic") => snapshot.ic = number(single()?)?,
+                (_, "registers") => {
+                    for &token in &values {
+                        let mut parts = token.splitn(2, '=');
+                        let reg = parts.next().and_then(named_register);
+                        let value = parts.next().and_then(|value| value.parse().ok());
+                        match (reg, value) {
+                            (Some(reg), Some(value)) => snapshot.registers[reg] = value,
+                            _ => return Err(Error::at_token(line_no, &line, token, format!("invalid register value: {}", token))),
+                        }
+                    }
+                },
+                (_, "sound") => snapshot.last_sound = Some(number(single()?)?),
+                (_, "messages") => {
+                    for &token in &values {
+                        snapshot.messages.push_back(number(token)?);
+                    }
+                },
+                (_, "lines") => {
+                    for &token in &values {
+                        match token.parse::<usize>() {
+                            Ok(line) if line > 0 => snapshot.lines.push(line),
+                            _ => return Err(Error::at_token(line_no, &line, token, format!("invalid line: {}", token))),
+                        }
+                    }
+                },
+                (_, "program") => length = Some(number(single()?)?),
+                _ => return Err(Error::at_token(line_no, &line, key, format!("unknown field: {}", key))),
+            }
+        }
+
+        snapshot.dialect = dialect.ok_or_else(|| Error::invalid_state("snapshot without a dialect"))?;
+        match length {
+            Some(length) if length == snapshot.instructions.len() as i64 => (),
+            Some(length) => return Err(Error::invalid_state(format!("expected {} instructions, found {}", length, snapshot.instructions.len()))),
+            None => return Err(Error::invalid_state("snapshot without a program")),
+        }
+        if snapshot.lines.is_empty() {
+            snapshot.lines.extend(1 ..= snapshot.instructions.len());
+        }
+        if snapshot.lines.len() != snapshot.instructions.len() {
+            return Err(Error::invalid_state(format!("expected {} lines, found {}", snapshot.instructions.len(), snapshot.lines.len())));
+        }
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Assembler, assemble};
+
+    #[test]
+    fn test_snapshot() {
+        let program = assemble("snd 5\nrcv a\nadd b a\nrcv c\nadd c b").unwrap();
+        let mut assembler = Assembler::with_program(1, Dialect::Duet, &program).unwrap();
+        assembler.push_message(4);
+        assembler.push_message(8);
+        assembler.run(3).unwrap();
+
+        let snapshot = assembler.snapshot();
+        let mut text = Vec::new();
+        snapshot.write(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(text, "snapshot 1\ndialect duet\narithmetic checked remainder\nic 3\nregisters a=4 b=4 p=1\nmessages 8\n\
+                          program 5\nsnd 5\nrcv a\nadd b a\nrcv c\nadd c b\n");
+        assert_eq!(Snapshot::read(text.as_bytes()).unwrap(), snapshot);
+
+        let mut fork = Assembler::from_snapshot(snapshot.clone()).unwrap();
+        fork.registers[1] = 100;
+        fork.run(u64::MAX).unwrap();
+        assembler.run(u64::MAX).unwrap();
+        assert_eq!((assembler.register('c'), fork.register('c')), (12, 108));
+
+        let mut sound = Assembler::with_program(0, Dialect::Sound, &assemble("snd 7\nset a 1").unwrap()).unwrap();
+        sound.run(1).unwrap();
+        assert_eq!(Snapshot::read(&b"snapshot 1\ndialect sound\nic 1\nregisters\nsound 7\nmessages\nprogram 2\nsnd 7\nset a 1\n"[..]).unwrap(),
+                   sound.snapshot());
+
+        assert_eq!(Snapshot::read(&b"snapshot 1\ndialect duet\nic x\n"[..]).unwrap_err().to_string(),
+                   "parse error at 3:4: invalid number: x");
+        assert_eq!(Snapshot::read(&b"snapshot 1\ndialect duet\nprogram 1\nsnd\n"[..]).unwrap_err().to_string().split(':').next(),
+                   Some("parse error at 4"));
+        let mut listed = Assembler::new(0, Dialect::Duet);
+        listed.load_program_str("snd 1\n\nrcv a\n").unwrap();
+        let mut text = Vec::new();
+        listed.snapshot().write(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.ends_with("messages\nlines 1 3\nprogram 2\nsnd 1\nrcv a\n"));
+        assert_eq!(Assembler::from_snapshot(Snapshot::read(text.as_bytes()).unwrap()).unwrap().line(1), Some(3));
+        assert_eq!(Snapshot::read(&b"snapshot 1\ndialect duet\nlines 2\nprogram 2\nsnd 1\nsnd 2\n"[..]).unwrap_err().to_string(),
+                   "invalid state: expected 2 lines, found 1");
+        let coprocessor = b"snapshot 1\ndialect coprocessor\nmessages\nprogram 1\nsnd 1\n";
+        assert!(Assembler::from_snapshot(Snapshot::read(&coprocessor[..]).unwrap()).is_err());
+    }
+}
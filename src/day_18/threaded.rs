@@ -0,0 +1,194 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use error::Error;
+use super::assembler::Assembler;
+use super::instruction::Instruction;
+use super::network::{Outcome, Topology};
+
+enum Message {
+    Value(i64),
+    Stop,
+}
+
+/// What a worker tells the coordinator, `delivered` and `pulled` count channel sends and receives.
+enum Event {
+    Blocked { id: usize, delivered: u64, pulled: u64 },
+    Woke { id: usize },
+    Terminated { id: usize, delivered: u64, pulled: u64 },
+    /// A value reached a program that already terminated.
+    Drained { id: usize },
+    /// The network ran out of steps before the program could execute its next instruction.
+    Exhausted,
+    Fault { id: usize, error: Error },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Running,
+    Blocked,
+    Terminated,
+}
+
+/// Program handed back by its thread once the network stopped.
+pub struct Finished {
+    pub assembler: Assembler,
+    pub sent: u64,
+    pub received: u64,
+    pub steps: u64,
+}
+
+struct Worker {
+    id: usize,
+    assembler: Assembler,
+    inbox: Receiver<Message>,
+    peers: Vec<Sender<Message>>,
+    events: Sender<Event>,
+    stop: Arc<AtomicBool>,
+    /// Instructions executed by the whole network, none may start once it reached `limit`.
+    executed: Arc<AtomicU64>,
+    limit: u64,
+    delivered: u64,
+    pulled: u64,
+}
+
+impl Worker {
+    fn run(mut self) -> Finished {
+        let (mut sent, mut received, mut steps) = (0, 0, 0);
+        while !self.stop.load(Ordering::Relaxed) {
+            if self.assembler.terminated() {
+                self.report(Event::Terminated { id: self.id, delivered: self.delivered, pulled: self.pulled });
+                while let Ok(Message::Value(_)) = self.inbox.recv() {
+                    self.report(Event::Drained { id: self.id });
+                }
+                break;
+            }
+
+            if self.assembler.waiting_message() {
+                let message = match self.inbox.try_recv() {
+                    Ok(message) => Ok(message),
+                    Err(TryRecvError::Empty) => {
+                        self.report(Event::Blocked { id: self.id, delivered: self.delivered, pulled: self.pulled });
+                        let message = self.inbox.recv();
+                        if let Ok(Message::Value(_)) = message {
+                            self.report(Event::Woke { id: self.id });
+                        }
+                        message.map_err(|_| ())
+                    },
+                    Err(TryRecvError::Disconnected) => Err(()),
+                };
+                match message {
+                    Ok(Message::Value(value)) => {
+                        self.pulled += 1;
+                        self.assembler.push_message(value);
+                    },
+                    _ => break,
+                }
+                continue;
+            }
+
+            if self.executed.fetch_add(1, Ordering::Relaxed) >= self.limit {
+                self.report(Event::Exhausted);
+                break;
+            }
+            if let Some(Instruction::Rcv(_)) = self.assembler.current() {
+                received += 1;
+            }
+            match self.assembler.execute_next_instruction() {
+                Ok(Some(value)) => {
+                    sent += 1;
+                    for peer in &self.peers {
+                        if peer.send(Message::Value(value)).is_ok() {
+                            self.delivered += 1;
+                        }
+                    }
+                },
+                Ok(None) => (),
+                Err(error) => {
+                    self.report(Event::Fault { id: self.id, error });
+                    break;
+                },
+            }
+            steps += 1;
+        }
+
+        Finished { assembler: self.assembler, sent, received, steps }
+    }
+
+    fn report(&self, event: Event) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// Runs every program on its own thread until all of them terminated or are blocked on `rcv` with no value in flight,
+/// or until they executed `limit` instructions between them.
+pub fn run(programs: Vec<Assembler>, topology: Topology, limit: u64) -> Result<(Outcome, Vec<Finished>), Error> {
+    let count = programs.len();
+    let (inboxes, receivers): (Vec<_>, Vec<_>) = (0 .. count).map(|_| mpsc::channel()).unzip();
+    let (events, coordinator) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let executed = Arc::new(AtomicU64::new(0));
+
+    let handles = programs.into_iter().zip(receivers).enumerate().map(|(id, (assembler, inbox))| {
+        let worker = Worker {
+            id,
+            assembler,
+            inbox,
+            peers: topology.destinations(id, count).into_iter().map(|peer| inboxes[peer].clone()).collect(),
+            events: events.clone(),
+            stop: stop.clone(),
+            executed: executed.clone(),
+            limit,
+            delivered: 0,
+            pulled: 0,
+        };
+        thread::spawn(move || worker.run())
+    }).collect::<Vec<_>>();
+    drop(events);
+
+    let mut status = vec![Status::Running; count];
+    let mut delivered = vec![0; count];
+    let mut pulled = vec![0; count];
+    let outcome = loop {
+        match coordinator.recv() {
+            Ok(Event::Blocked { id, delivered: d, pulled: p }) => {
+                status[id] = Status::Blocked;
+                delivered[id] = d;
+                pulled[id] = p;
+            },
+            Ok(Event::Woke { id }) => status[id] = Status::Running,
+            Ok(Event::Terminated { id, delivered: d, pulled: p }) => {
+                status[id] = Status::Terminated;
+                delivered[id] = d;
+                pulled[id] = p;
+            },
+            Ok(Event::Drained { id }) => pulled[id] += 1,
+            Ok(Event::Exhausted) => break Ok(Outcome::StepLimit),
+            Ok(Event::Fault { id, error }) => break Err((id, error)),
+            Err(_) => break Err((0, Error::invalid_state("every program stopped without reporting"))),
+        }
+
+        if status.iter().all(|&status| status == Status::Terminated) {
+            break Ok(Outcome::Halted);
+        }
+        let idle = status.iter().all(|&status| status != Status::Running);
+        if idle && delivered.iter().sum::<u64>() == pulled.iter().sum::<u64>() {
+            break Ok(Outcome::Deadlock);
+        }
+    };
+
+    stop.store(true, Ordering::Relaxed);
+    for inbox in &inboxes {
+        let _ = inbox.send(Message::Stop);
+    }
+    let mut finished = Vec::with_capacity(count);
+    for (id, handle) in handles.into_iter().enumerate() {
+        finished.push(handle.join().map_err(|_| Error::invalid_state(format!("program {} panicked", id)))?);
+    }
+
+    match outcome {
+        Ok(outcome) => Ok((outcome, finished)),
+        Err((id, error)) => Err(Error::invalid_state(format!("program {}: {}", id, error))),
+    }
+}
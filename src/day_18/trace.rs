@@ -0,0 +1,283 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use error::Error;
+use super::diagnostic::OPCODES;
+use super::instruction::{Instruction, Register, named_register, register_name};
+use super::registers::Registers;
+
+/// Steps between the register files kept by a `Replay` to seek quickly.
+const CHECKPOINT_INTERVAL: usize = 1024;
+
+/// One executed instruction with the registers it changed, as `(register, new value)`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Step {
+    pub ic: i64,
+    pub opcode: &'static str,
+    pub changes: Vec<(Register, i64)>,
+    pub sent: Option<i64>,
+    pub received: Option<i64>,
+}
+
+/// Why a traced run stopped after its last step.
+#[derive(Clone, PartialEq, Debug)]
+pub enum End {
+    Terminated,
+    /// Blocked on `rcv` with no message to receive.
+    Waiting,
+    /// Stopped by the step limit while the program could still run.
+    Limit,
+    /// Stopped by the error with this message.
+    Error(String),
+}
+
+/// Registers at the start of a run and every step executed since.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Trace {
+    pub initial: Registers,
+    pub steps: Vec<Step>,
+    /// `None` while the run is still recording.
+    pub end: Option<End>,
+}
+
+/// Machine state after the first `step` steps of a trace, `ic` is `None` past the last one.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Frame {
+    pub step: usize,
+    pub ic: Option<i64>,
+    pub registers: Registers,
+}
+
+/// Random access to the states of a trace.
+pub struct Replay<'a> {
+    trace: &'a Trace,
+    checkpoints: Vec<Registers>,
+}
+
+impl Trace {
+    pub fn new(initial: Registers) -> Trace {
+        Trace { initial, steps: Vec::new(), end: None }
+    }
+
+    /// Appends the step executing `instruction` at `ic`, which turned `before` into `after`, the run goes on.
+    pub fn record(&mut self, ic: i64, instruction: &Instruction, before: &Registers, after: &Registers, sent: Option<i64>, received: Option<i64>) {
+        self.end = None;
+        let changes = before.diff(after).into_iter().map(|(reg, _, value)| (reg, value)).collect();
+        self.steps.push(Step { ic, opcode: instruction.opcode(), changes, sent, received });
+    }
+
+    /// Writes a header, the non-zero initial registers, then one line per step:
+    /// `ic opcode [register=value]... [>sent] [<received]`.
+    /// A last `end terminated|waiting|limit|error <message>` line tells why the run stopped.
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        writeln!(output, "trace 1")?;
+        let initial = self.initial.iter().map(|(reg, value)| format!(" {}={}", register_name(reg), value)).collect::<String>();
+        writeln!(output, "init{}", initial)?;
+        for step in &self.steps {
+            write!(output, "{} {}", step.ic, step.opcode)?;
+            for &(reg, value) in &step.changes {
+                write!(output, " {}={}", register_name(reg), value)?;
+            }
+            if let Some(value) = step.sent {
+                write!(output, " >{}", value)?;
+            }
+            if let Some(value) = step.received {
+                write!(output, " <{}", value)?;
+            }
+            writeln!(output)?;
+        }
+        match self.end {
+            Some(End::Terminated) => writeln!(output, "end terminated")?,
+            Some(End::Waiting) => writeln!(output, "end waiting")?,
+            Some(End::Limit) => writeln!(output, "end limit")?,
+            Some(End::Error(ref message)) => writeln!(output, "end error {}", message)?,
+            None => (),
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(input: R) -> Result<Trace, Error> {
+        let mut lines = input.lines();
+        let header = lines.next().transpose()?;
+        if header.as_ref().map(|header| header.trim()) != Some("trace 1") {
+            return Err(Error::parse(1, 1, "expected header: trace 1"));
+        }
+
+        let mut trace = Trace::new(Registers::new());
+        let line = lines.next().ok_or_else(|| Error::parse(2, 1, "expected initial registers"))??;
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("init") {
+            return Err(Error::parse(2, 1, "expected initial registers"));
+        }
+        for token in tokens {
+            let (reg, value) = parse_change(token).ok_or_else(|| Error::at_token(2, &line, token, format!("invalid register value: {}", token)))?;
+            trace.initial[reg] = value;
+        }
+
+        for (idx, line) in lines.enumerate() {
+            let line = line?;
+            let line_no = idx + 3;
+            if trace.end.is_some() {
+                return Err(Error::parse(line_no, 1, "unexpected line after end"));
+            }
+            if let Some(reason) = line.trim().strip_prefix("end") {
+                trace.end = Some(match reason.trim() {
+                    "terminated" => End::Terminated,
+                    "waiting" => End::Waiting,
+                    "limit" => End::Limit,
+                    reason if reason.starts_with("error") => End::Error(reason["error".len() ..].trim().to_string()),
+                    _ => return Err(Error::parse(line_no, 1, "expected terminated, waiting, limit or error after end")),
+                });
+                continue;
+            }
+            let mut tokens = line.split_whitespace();
+            let ic = tokens.next()
+                           .and_then(|ic| ic.parse::<i64>().ok())
+                           .ok_or_else(|| Error::parse(line_no, 1, "expected instruction pointer"))?;
+            let opcode = tokens.next()
+                               .and_then(|op| OPCODES.iter().find(|&&known| known == op))
+                               .ok_or_else(|| Error::parse(line_no, 1, "expected opcode"))?;
+
+            let mut step = Step { ic, opcode, changes: Vec::new(), sent: None, received: None };
+            for token in tokens {
+                let invalid = || Error::at_token(line_no, &line, token, format!("invalid step entry: {}", token));
+                if let Some(value) = token.strip_prefix('>') {
+                    step.sent = Some(value.parse().map_err(|_| invalid())?);
+                }
+                else if let Some(value) = token.strip_prefix('<') {
+                    step.received = Some(value.parse().map_err(|_| invalid())?);
+                }
+                else {
+                    step.changes.push(parse_change(token).ok_or_else(invalid)?);
+                }
+            }
+            trace.steps.push(step);
+        }
+        Ok(trace)
+    }
+}
+
+fn parse_change(token: &str) -> Option<(Register, i64)> {
+    let mut parts = token.splitn(2, '=');
+    let reg = named_register(parts.next()?)?;
+    Some((reg, parts.next()?.parse().ok()?))
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(trace: &'a Trace) -> Replay<'a> {
+        let mut checkpoints = vec![trace.initial.clone()];
+        let mut registers = trace.initial.clone();
+        for (idx, step) in trace.steps.iter().enumerate() {
+            for &(reg, value) in &step.changes {
+                registers[reg] = value;
+            }
+            if (idx + 1) % CHECKPOINT_INTERVAL == 0 {
+                checkpoints.push(registers.clone());
+            }
+        }
+        Replay { trace, checkpoints }
+    }
+
+    pub fn len(&self) -> usize {
+        self.trace.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trace.steps.is_empty()
+    }
+
+    /// State after the first `step` steps, `None` past the end of the trace.
+    pub fn seek(&self, step: usize) -> Option<Frame> {
+        if step > self.len() {
+            return None;
+        }
+
+        let checkpoint = step / CHECKPOINT_INTERVAL;
+        let mut registers = self.checkpoints[checkpoint].clone();
+        for executed in &self.trace.steps[checkpoint * CHECKPOINT_INTERVAL .. step] {
+            for &(reg, value) in &executed.changes {
+                registers[reg] = value;
+            }
+        }
+        Some(Frame { step, ic: self.trace.steps.get(step).map(|next| next.ic), registers })
+    }
+
+    /// Registers differing between the states after `first` and `second` steps, as `(register, first, second)`.
+    pub fn diff(&self, first: usize, second: usize) -> Option<Vec<(Register, i64, i64)>> {
+        let (lhs, rhs) = (self.seek(first)?, self.seek(second)?);
+        Some(lhs.registers.diff(&rhs.registers))
+    }
+
+    /// Fewest steps after which `reg` holds `value`.
+    pub fn find(&self, reg: Register, value: i64) -> Option<usize> {
+        if self.trace.initial[reg] == value {
+            return Some(0);
+        }
+        self.trace.steps.iter()
+                        .position(|step| step.changes.iter().any(|&(changed, new)| changed == reg && new == value))
+                        .map(|idx| idx + 1)
+    }
+}
+
+impl fmt::Display for End {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            End::Terminated => write!(f, "terminated"),
+            End::Waiting => write!(f, "waiting for a message"),
+            End::Limit => write!(f, "step limit reached"),
+            End::Error(ref message) => write!(f, "stopped by error: {}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Assembler, Dialect, assemble};
+
+    #[test]
+    fn test_trace() {
+        let program = assemble("set a 3\nadd b a\nadd a -1\njgz a -2\nsnd b\nrcv c").unwrap();
+        let mut assembler = Assembler::with_program(1, Dialect::Duet, &program).unwrap();
+        assembler.enable_trace();
+        assembler.push_message(42);
+        assembler.run(100).unwrap();
+
+        let trace = assembler.trace().unwrap();
+        assert_eq!(trace.steps.len(), 12);
+        let mut text = Vec::new();
+        trace.write(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("trace 1\ninit p=1\n0 set a=3\n1 add b=3\n2 add a=2\n3 jgz\n"));
+        assert!(text.ends_with("4 snd >6\n5 rcv c=42 <42\nend terminated\n"));
+        assert_eq!(&Trace::read(text.as_bytes()).unwrap(), trace);
+
+        let replay = Replay::new(trace);
+        let frame = replay.seek(5).unwrap();
+        assert_eq!((frame.ic, frame.registers[0], frame.registers[1]), (Some(2), 2, 5));
+        assert_eq!(replay.seek(12).unwrap().ic, None);
+        assert_eq!(replay.seek(13), None);
+        assert_eq!(replay.diff(2, 5).unwrap(), vec![(0, 3, 2), (1, 3, 5)]);
+        assert_eq!(replay.find(1, 5), Some(5));
+        assert_eq!(replay.find(15, 1), Some(0));
+        assert_eq!(replay.find(2, 7), None);
+
+        let mut limited = Assembler::with_program(0, Dialect::Duet, &assemble("add a 1\njgz 1 -1").unwrap()).unwrap();
+        limited.enable_trace();
+        limited.run(5).unwrap();
+        assert_eq!(limited.trace().unwrap().end, Some(End::Limit));
+        let mut faulty = Assembler::with_program(0, Dialect::Duet, &assemble("set a 1\nmod a 0").unwrap()).unwrap();
+        faulty.enable_trace();
+        faulty.run(5).unwrap_err();
+        let mut text = Vec::new();
+        faulty.trace().unwrap().write(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.ends_with("0 set a=1\nend error fault: ic 1: mod by zero: mod a 0\n"));
+        let end = Trace::read(text.as_bytes()).unwrap().end.unwrap();
+        assert_eq!(end.to_string(), "stopped by error: fault: ic 1: mod by zero: mod a 0");
+
+        assert_eq!(Trace::read("trace 1\ninit\nend limit\n0 snd 1\n".as_bytes()).unwrap_err().to_string(),
+                   "parse error at 4:1: unexpected line after end");
+        assert_eq!(Trace::read("trace 1\ninit\n3 jgz x\n".as_bytes()).unwrap_err().to_string(),
+                   "parse error at 3:7: invalid step entry: x");
+    }
+}
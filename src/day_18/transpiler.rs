@@ -0,0 +1,185 @@
+use std::fmt::Write;
+use super::arithmetic::{Arithmetic, Modulo, Overflow};
+use super::instruction::{Instruction, Operand, Register, register_name};
+
+fn local(reg: Register) -> String {
+    format!("reg_{}", register_name(reg))
+}
+
+fn value(operand: Operand) -> String {
+    match operand {
+        Operand::Reg(reg) => local(reg),
+        Operand::Const(i64::MIN) => String::from("i64::MIN"),
+        Operand::Const(c) => c.to_string(),
+    }
+}
+
+/// `target = target op rhs` under `arithmetic`, breaking out of the loop with `Err(pc)` on a fault.
+fn arithmetic_arm(arithmetic: Arithmetic, op: &str, target: Register, rhs: Operand) -> String {
+    let (target, rhs) = (local(target), value(rhs));
+    let method = match (op, arithmetic.modulo) {
+        ("rem", Modulo::Euclidean) => String::from("rem_euclid"),
+        _ => String::from(op),
+    };
+    match arithmetic.overflow {
+        Overflow::Checked => {
+            format!("{} = match {}.checked_{}({}) {{ Some(value) => value, None => break Err(pc) }},", target, target, method, rhs)
+        },
+        Overflow::Wrapping | Overflow::Saturating if op == "rem" => match rhs.as_str() {
+            "0" => String::from("break Err(pc),"),
+            _ if rhs.starts_with("reg_") => {
+                format!("{} = match {} {{ 0 => break Err(pc), divisor => {}.wrapping_{}(divisor) }},", target, rhs, target, method)
+            },
+            _ => format!("{} = {}.wrapping_{}({}),", target, target, method, rhs),
+        },
+        Overflow::Wrapping => format!("{} = {}.wrapping_{}({}),", target, target, method, rhs),
+        Overflow::Saturating => format!("{} = {}.saturating_{}({}),", target, target, method, rhs),
+    }
+}
+
+/// Jumps to `target` when `x` compares to 0 with `op`, decided here when `x` is a constant.
+/// A target outside `i64` faults like the interpreter does.
+fn jump_arm(ic: usize, x: Operand, op: &str, offset: Operand) -> String {
+    let jump = match offset {
+        Operand::Const(offset) => match (ic as i64).checked_add(offset) {
+            Some(target) => format!("{{ pc = {}; continue; }},", target),
+            None => String::from("{ break Err(pc) },"),
+        },
+        Operand::Reg(reg) => format!("{{ pc = match pc.checked_add({}) {{ Some(target) => target, None => break Err(pc) }}; continue; }},", local(reg)),
+    };
+    match x {
+        Operand::Const(c) if (op == ">" && c > 0) || (op == "!=" && c != 0) => jump,
+        Operand::Const(_) => String::from("{},"),
+        Operand::Reg(reg) => format!("if {} {} 0 {}", local(reg), op, jump),
+    }
+}
+
+/// Rust source of a standalone function named `name` running `program` under `arithmetic`.
+///
+/// Every register the program uses becomes a local, loaded from and stored back to the `registers` array,
+/// in the order listed by the generated documentation. `snd` is called with every value sent or played.
+/// `rcv` is called with the current value of its register and returns the new one, or `None` to stop there,
+/// so a Sound dialect caller recovers the last sound when given a non-zero value.
+/// The function starts at instruction `start` and returns `Ok` with the instruction it stopped at,
+/// past the end or on a `rcv`, and `Err` with the instruction that faulted.
+pub fn transpile(program: &[Instruction], name: &str, arithmetic: Arithmetic) -> String {
+    let mut registers = program.iter().flat_map(|instruction| instruction.reads().into_iter().chain(instruction.writes())).collect::<Vec<_>>();
+    registers.sort();
+    registers.dedup();
+    let written = |reg: Register| program.iter().any(|instruction| instruction.writes() == Some(reg));
+    let sends = program.iter().any(|instruction| matches!(instruction, Instruction::Snd(_)));
+    let receives = program.iter().any(|instruction| matches!(instruction, Instruction::Rcv(_)));
+
+    let names = registers.iter().map(|&reg| register_name(reg)).collect::<Vec<_>>();
+    let bindings = registers.iter().map(|&reg| format!("{}{}", if written(reg) { "mut " } else { "" }, local(reg))).collect::<Vec<_>>();
+    let locals = registers.iter().map(|&reg| local(reg)).collect::<Vec<_>>();
+
+    let mut text = String::new();
+    let _ = writeln!(text, "/// Transpiled from {} instructions, `registers` holds {} in this order.", program.len(), names.join(", "));
+    let _ = writeln!(text, "pub fn {}<S, R>(registers: &mut [i64; {}], start: i64, {}snd: S, {}rcv: R) -> Result<i64, i64>",
+                     name, registers.len(), if sends { "mut " } else { "_" }, if receives { "mut " } else { "_" });
+    let _ = writeln!(text, "    where S: FnMut(i64), R: FnMut(i64) -> Option<i64>");
+    let _ = writeln!(text, "{{");
+    let _ = writeln!(text, "    let [{}] = *registers;", bindings.join(", "));
+    let _ = writeln!(text, "    let mut pc = start;");
+    let _ = writeln!(text, "    let exit = loop {{");
+    let _ = writeln!(text, "        match pc {{");
+    for (ic, instruction) in program.iter().enumerate() {
+        let arm = match *instruction {
+            Instruction::Snd(x) => format!("snd({}),", value(x)),
+            Instruction::Set(r, y) => format!("{} = {},", local(r), value(y)),
+            Instruction::Add(r, y) => arithmetic_arm(arithmetic, "add", r, y),
+            Instruction::Sub(r, y) => arithmetic_arm(arithmetic, "sub", r, y),
+            Instruction::Mul(r, y) => arithmetic_arm(arithmetic, "mul", r, y),
+            Instruction::Mod(r, y) => arithmetic_arm(arithmetic, "rem", r, y),
+            Instruction::Rcv(r) => format!("match rcv({}) {{ Some(value) => {} = value, None => break Ok(pc) }},", local(r), local(r)),
+            Instruction::Jgz(x, y) => jump_arm(ic, x, ">", y),
+            Instruction::Jnz(x, y) => jump_arm(ic, x, "!=", y),
+        };
+        let _ = writeln!(text, "            {} => {}", ic, arm);
+    }
+    let _ = writeln!(text, "            _ => break Ok(pc),");
+    let _ = writeln!(text, "        }}");
+    let _ = writeln!(text, "        pc += 1;");
+    let _ = writeln!(text, "    }};");
+    let _ = writeln!(text, "    *registers = [{}];", locals.join(", "));
+    let _ = writeln!(text, "    exit");
+    let _ = writeln!(text, "}}");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Assembler, Dialect, assemble};
+
+    const PROGRAM: &str = "set i 5\nrcv a\nmul a 3\nmod a 7\nsnd a\nadd i -1\njgz i -5\njgz 1 2\nmul b 2\nadd b i";
+
+    // Output of `transpile(PROGRAM, "generated", Arithmetic::default())`, compiled to check it against the interpreter.
+    /// Transpiled from 10 instructions, `registers` holds a, b, i in this order.
+    pub fn generated<S, R>(registers: &mut [i64; 3], start: i64, mut snd: S, mut rcv: R) -> Result<i64, i64>
+        where S: FnMut(i64), R: FnMut(i64) -> Option<i64>
+    {
+        let [mut reg_a, mut reg_b, mut reg_i] = *registers;
+        let mut pc = start;
+        let exit = loop {
+            match pc {
+                0 => reg_i = 5,
+                1 => match rcv(reg_a) { Some(value) => reg_a = value, None => break Ok(pc) },
+                2 => reg_a = match reg_a.checked_mul(3) { Some(value) => value, None => break Err(pc) },
+                3 => reg_a = match reg_a.checked_rem(7) { Some(value) => value, None => break Err(pc) },
+                4 => snd(reg_a),
+                5 => reg_i = match reg_i.checked_add(-1) { Some(value) => value, None => break Err(pc) },
+                6 => if reg_i > 0 { pc = 1; continue; },
+                7 => { pc = 9; continue; },
+                8 => reg_b = match reg_b.checked_mul(2) { Some(value) => value, None => break Err(pc) },
+                9 => reg_b = match reg_b.checked_add(reg_i) { Some(value) => value, None => break Err(pc) },
+                _ => break Ok(pc),
+            }
+            pc += 1;
+        };
+        *registers = [reg_a, reg_b, reg_i];
+        exit
+    }
+
+    #[test]
+    fn test_transpile() {
+        let program = assemble(PROGRAM).unwrap();
+        let source = transpile(&program, "generated", Arithmetic::default());
+        let expected = include_str!("transpiler.rs").lines()
+                                                    .skip_while(|line| !line.contains("/// Transpiled from 10"))
+                                                    .take_while(|line| *line != "    }")
+                                                    .map(|line| format!("{}\n", line.get(4 ..).unwrap_or("")))
+                                                    .collect::<String>();
+        assert_eq!(source, expected + "}\n");
+
+        let mut interpreter = Assembler::with_program(0, Dialect::Duet, &program).unwrap();
+        let mut sent = Vec::new();
+        let mut registers = [0; 3];
+        let mut pc = 0;
+        for message in &[4, -9, 11, 100, 2] {
+            interpreter.push_message(*message);
+            while !interpreter.terminated() && !interpreter.waiting_message() {
+                interpreter.execute_next_instruction().unwrap();
+            }
+            let mut inbox = Some(*message);
+            pc = generated(&mut registers, pc, |value| sent.push(value), |_| inbox.take()).unwrap();
+        }
+        assert_eq!(pc, 10);
+        assert_eq!(sent, vec![5, -6, 5, 6, 6]);
+        assert_eq!(registers.to_vec(), ["a", "b", "i"].iter().map(|name| interpreter.registers[super::super::named_register(name).unwrap()]).collect::<Vec<_>>());
+
+        let mut registers = [i64::MAX / 2, 0, 0];
+        assert_eq!(generated(&mut registers, 2, |_| (), |_| None), Err(2));
+
+        let wrapping = Arithmetic { overflow: Overflow::Wrapping, modulo: Modulo::Euclidean };
+        let source = transpile(&assemble("mod a b\nmod a 0\nmod a 3\nadd a 1").unwrap(), "f", wrapping);
+        assert!(source.contains("0 => reg_a = match reg_b { 0 => break Err(pc), divisor => reg_a.wrapping_rem_euclid(divisor) },\n"));
+        assert!(source.contains("1 => break Err(pc),\n            2 => reg_a = reg_a.wrapping_rem_euclid(3),\n            3 => reg_a = reg_a.wrapping_add(1),\n"));
+        assert!(source.contains("pub fn f<S, R>(registers: &mut [i64; 2], start: i64, _snd: S, _rcv: R)"));
+
+        let source = transpile(&assemble("jgz a b\njgz 1 9223372036854775807").unwrap(), "f", Arithmetic::default());
+        assert!(source.contains("0 => if reg_a > 0 { pc = match pc.checked_add(reg_b) { Some(target) => target, None => break Err(pc) }; continue; },\n"));
+        assert!(source.contains("1 => { break Err(pc) },\n"));
+    }
+}
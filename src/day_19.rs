@@ -1,17 +1,39 @@
 use std::collections::HashMap;
-use std::io::prelude::*; 
-use std::fs::File; 
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let mut diag = RoutingDiagram::from_file("routing.txt");
-    while diag.next_move() {}
-    format!("{}", diag.letters.iter().collect::<String>())
-}
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Input = RoutingDiagram;
+
+    fn day(&self) -> u32 {
+        19
+    }
+
+    fn name(&self) -> &'static str {
+        "A Series of Tubes"
+    }
 
-pub fn second_puzzle() -> String {
-    let mut diag = RoutingDiagram::from_file("routing.txt");
-    while diag.next_move() {}
-    format!("{}", diag.steps + 1)
+    fn input_path(&self) -> &'static str {
+        "routing.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<RoutingDiagram, Error> {
+        RoutingDiagram::from_str(input)
+    }
+
+    fn first_puzzle(&self, diag: &RoutingDiagram) -> Result<String, Error> {
+        let mut diag = diag.clone();
+        while diag.next_move() {}
+        Ok(diag.letters.iter().collect::<String>())
+    }
+
+    fn second_puzzle(&self, diag: &RoutingDiagram) -> Result<String, Error> {
+        let mut diag = diag.clone();
+        while diag.next_move() {}
+        Ok(format!("{}", diag.steps + 1))
+    }
 }
 
 #[derive(PartialEq)]
@@ -33,7 +55,8 @@ impl Pos {
 
 type Dim = Pos;
 
-struct RoutingDiagram {
+#[derive(Clone)]
+pub struct RoutingDiagram {
     diagram: HashMap<(usize, usize), char>,
     dims: Dim,
     pos: Pos,
@@ -95,7 +118,7 @@ impl RoutingDiagram {
        } 
     }
 
-    fn from_str(lines: &str) -> RoutingDiagram {
+    fn from_str(lines: &str) -> Result<RoutingDiagram, Error> {
         let mut diagram = RoutingDiagram::new();
         let mut height  = 0;
         let mut width = 0;
@@ -105,18 +128,8 @@ impl RoutingDiagram {
         }
 
         diagram.dims = Dim::new(width, height); 
-        diagram.pos = diagram.find_start_pos();
-        diagram
-    }
-
-    fn from_file(path: &str) -> RoutingDiagram {
-        let mut file = File::open(path).expect("Failed to open routing file.");
-        let mut buff = String::new();
-        if let Ok(_) = file.read_to_string(&mut buff) {
-            return RoutingDiagram::from_str(&buff)
-        }
-
-        panic!("Failed to read routing file.");
+        diagram.pos = diagram.find_start_pos()?;
+        Ok(diagram)
     }
 
     fn parse_row(&mut self, y: usize, line: &str) -> usize {
@@ -259,16 +272,15 @@ impl RoutingDiagram {
         }
     }
 
-    fn find_start_pos(&self) -> Pos {
+    fn find_start_pos(&self) -> Result<Pos, Error> {
         for x in 0 .. self.dims.x {
             let pos = Pos::new(x, 0);
-            match self.get(&pos) {
-                Field::VertLine => return Pos::new(x, 0),
-                _ => {}
+            if self.get(&pos) == Field::VertLine {
+                return Ok(pos)
             }
         }
 
-        panic!("Failed to find starting position.")
+        Err(Error::no_solution("failed to find starting position"))
     }
 
     fn next_move(&mut self) -> bool {
@@ -316,7 +328,7 @@ impl RoutingDiagram {
             return true
         }
 
-        return false
+        false
     }
 }
 
@@ -332,7 +344,7 @@ mod tests {
         F---|----E|--+ 
             |  |  |  D 
             +B-+  +--+ 
-            ");
+            ").unwrap();
 
         println!("dims: {}x{}", diag.dims.x, diag.dims.y);
         println!("start: {}x{}", diag.pos.x, diag.pos.y);
@@ -367,7 +379,7 @@ mod tests {
         F---|----E|--+ 
             |  |  |  D 
             +B-+  +--+ 
-            ");
+            ").unwrap();
         while diag.next_move() {}
 
         assert_eq!(diag.steps + 1, 38);
@@ -1,24 +1,49 @@
-use std::fs::File;
-use std::io::prelude::*;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let sheet = from_file("spreadsheet.txt");
-    format!("{}", first_checksum(&sheet))
-}
+pub struct Day2;
 
-pub fn second_puzzle() -> String
+impl Solution for Day2
 {
-    let sheet = from_file("spreadsheet.txt");
-    format!("{}", second_checksum(&sheet))
+    type Input = Spreadsheet;
+
+    fn day(&self) -> u32
+    {
+        2
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Corruption Checksum"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "spreadsheet.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Spreadsheet, Error>
+    {
+        from_str(input)
+    }
+
+    fn first_puzzle(&self, sheet: &Spreadsheet) -> Result<String, Error>
+    {
+        Ok(format!("{}", first_checksum(sheet)))
+    }
+
+    fn second_puzzle(&self, sheet: &Spreadsheet) -> Result<String, Error>
+    {
+        Ok(format!("{}", second_checksum(sheet)))
+    }
 }
 
-type Spreadsheet = Vec<Vec<i32>>;
+pub type Spreadsheet = Vec<Vec<i32>>;
 
 fn first_checksum(sheet: &Spreadsheet) -> i32
 {
     let mut csum = 0;
-    for ref row in sheet.iter()
+    for row in sheet.iter()
     {
         if let (Some(max), Some(min)) = (row.iter().max(), row.iter().min())
         {
@@ -60,28 +85,24 @@ fn find_evenly_dividing_pair(numbers: &[i32]) -> Option<(i32, i32)>
     None
 }
 
-fn from_file(path: &str) -> Spreadsheet
-{
-    let mut file = File::open(path).expect("Failed to open spreadsheet");
-    let mut content = String::new();
-    file.read_to_string(&mut content).expect("Failed to read spreadsheet content");
-
-    from_str(content.as_str())
-}
-
-fn from_str(data: &str) -> Spreadsheet
+fn from_str(data: &str) -> Result<Spreadsheet, Error>
 {
     let mut sheet = Spreadsheet::new();
-    for line in data.split('\n')
+    for (idx, line) in data.split('\n').enumerate()
     {
         let row  = line.split_whitespace()
-                        .filter_map( |el|
-                            el.parse::<i32>().ok())
-                        .collect::<Vec<i32>>();
+                        .map( |el|
+                            match el.parse::<i32>()
+                            {
+                                Ok(0) => Err(Error::at_token(idx + 1, line, el, "cells have to be non-zero")),
+                                Ok(n) => Ok(n),
+                                Err(e) => Err(Error::at_token(idx + 1, line, el, format!("invalid cell {}: {}", el, e)))
+                            })
+                        .collect::<Result<Vec<i32>, Error>>()?;
         sheet.push(row);
     }
 
-    sheet
+    Ok(sheet)
 }
 
 
@@ -95,11 +116,7 @@ mod tests
             {
                 let mut sheet = Spreadsheet::new();
                 $(
-                    #[allow(unused_mut)]
-                    let mut row = Vec::new();
-                    $(
-                        row.push($x);
-                    )*
+                    let row = vec![$($x),*];
 
                     sheet.push(row);
                 )*
@@ -122,9 +139,9 @@ mod tests
     #[test]
     fn second_puzzle()
     {
-        assert_eq!(Some((2,8)), find_evenly_dividing_pair(&vec![5, 9, 2, 8]));
-        assert_eq!(Some((9,3)), find_evenly_dividing_pair(&vec![9, 4, 7, 3]));
-        assert_eq!(Some((3,6)), find_evenly_dividing_pair(&vec![3, 8, 6, 5]));
+        assert_eq!(Some((2,8)), find_evenly_dividing_pair(&[5, 9, 2, 8]));
+        assert_eq!(Some((9,3)), find_evenly_dividing_pair(&[9, 4, 7, 3]));
+        assert_eq!(Some((3,6)), find_evenly_dividing_pair(&[3, 8, 6, 5]));
         let sheet = spreadsheet![5,9,2,8; 9,4,7,3; 3,8,6,5];
         assert_eq!(9, second_checksum(&sheet));
     }
@@ -1,44 +1,63 @@
-use std::error::Error;
 use std::fmt;
-use simple_error::SimpleError;
-use std::io::prelude::*; 
-use std::fs::File; 
 use std::collections::HashMap;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let system = ParticleSystem::from_file("particles.txt").unwrap();
+pub struct Day20;
 
-    let mut min_acc_idx = 0;
-    let mut min_acc = system.particles[min_acc_idx].acc.manhatan_norm();
-    for (idx, p) in system.particles.iter().enumerate() {
-        if p.acc.manhatan_norm() < min_acc {
-            min_acc_idx = idx;
-            min_acc = p.acc.manhatan_norm();
-        }
-    }
+impl Solution for Day20 {
+    type Input = ParticleSystem;
 
-    format!("idx: {}\tacc: {}", min_acc_idx, min_acc)
-}
+    fn day(&self) -> u32 {
+        20
+    }
 
-pub fn second_puzzle() -> String {
+    fn name(&self) -> &'static str {
+        "Particle Swarm"
+    }
 
-    let mut system = ParticleSystem::from_file("particles.txt").unwrap();
+    fn input_path(&self) -> &'static str {
+        "particles.txt"
+    }
 
-    let mut particles_count = system.particles.len();
-    let mut repeat_count = 0;
+    fn parse(&self, input: &str) -> Result<ParticleSystem, Error> {
+        ParticleSystem::from_str(input)
+    }
 
-    while repeat_count < 10000 {
-        system.update();
-        system.remove_collisions();
-        if system.particles.len() == particles_count {
-            repeat_count += 1;
+    fn first_puzzle(&self, system: &ParticleSystem) -> Result<String, Error> {
+        let mut min_acc_idx = 0;
+        let mut min_acc = system.particles.first()
+                                .ok_or_else(|| Error::no_solution("empty particle system"))?
+                                .acc.manhatan_norm();
+        for (idx, p) in system.particles.iter().enumerate() {
+            if p.acc.manhatan_norm() < min_acc {
+                min_acc_idx = idx;
+                min_acc = p.acc.manhatan_norm();
+            }
         }
-        else {
-            particles_count = system.particles.len();
-            repeat_count = 0;
+
+        Ok(format!("idx: {}\tacc: {}", min_acc_idx, min_acc))
+    }
+
+    fn second_puzzle(&self, system: &ParticleSystem) -> Result<String, Error> {
+        let mut system = system.clone();
+
+        let mut particles_count = system.particles.len();
+        let mut repeat_count = 0;
+
+        while repeat_count < 10000 {
+            system.update();
+            system.remove_collisions();
+            if system.particles.len() == particles_count {
+                repeat_count += 1;
+            }
+            else {
+                particles_count = system.particles.len();
+                repeat_count = 0;
+            }
         }
+        Ok(format!("{}", particles_count))
     }
-    format!("{}", particles_count)
 }
 
 
@@ -55,18 +74,18 @@ impl Vec3 {
         Vec3{x, y, z}
     }
 
-    fn parse(s: &str) -> Result<Vec3, Box<Error>> {
+    fn parse(s: &str) -> Result<Vec3, Error> {
         let mut iter = s.split(',').map(|t| t.trim());
-        let x = iter.next()
-                    .ok_or(SimpleError::new(&format!("x missing ({0})", s)))?
-                    .parse::<i64>()?;
-        let y = iter.next()
-                    .ok_or(SimpleError::new(&format!("y missing ({0})", s)))?
-                    .parse::<i64>()?;
-        let z = iter.next()
-                    .ok_or(SimpleError::new(&format!("z missing ({0})", s)))?
-                    .parse::<i64>()?;
-
+        let mut coord = |name| {
+            let token = iter.next()
+                            .ok_or_else(|| Error::parse(1, s.len() + 1, format!("{} missing ({})", name, s)))?;
+            token.parse::<i64>()
+                 .map_err(|e| Error::at_token(1, s, token, format!("invalid {} ({}): {}", name, token, e)))
+        };
+
+        let x = coord("x")?;
+        let y = coord("y")?;
+        let z = coord("z")?;
         Ok(Vec3::new(x, y, z))
     }
 
@@ -99,20 +118,21 @@ impl Particle {
         Particle {pos, vel, acc}
     }
 
-    fn parse(line: &str) -> Result<Particle, Box<Error>> {
-        let mut iter = line.split(|ch| ch == '<' || ch == '>')
+    fn parse(line: &str) -> Result<Particle, Error> {
+        let mut iter = line.split(['<', '>'])
                            .map(|t| t.trim()).skip(1);
-                           
-        let pos_token = iter.next().ok_or(SimpleError::new(&format!("missing pos: {}", line)))?;
-        let pos = Vec3::parse(pos_token)?;
+        let missing = |name| Error::parse(1, line.len() + 1, format!("missing {}: {}", name, line));
+
+        let pos_token = iter.next().ok_or_else(|| missing("pos"))?;
+        let pos = Vec3::parse(pos_token).map_err(|e| e.within(line, pos_token))?;
         iter.next();
 
-        let vel_token = iter.next().ok_or(SimpleError::new(&format!("missing pos: {}", line)))?;
-        let vel = Vec3::parse(vel_token)?;
+        let vel_token = iter.next().ok_or_else(|| missing("vel"))?;
+        let vel = Vec3::parse(vel_token).map_err(|e| e.within(line, vel_token))?;
         iter.next();
 
-        let acc_token = iter.next().ok_or(SimpleError::new(&format!("missing pos: {}", line)))?;
-        let acc = Vec3::parse(acc_token)?;
+        let acc_token = iter.next().ok_or_else(|| missing("acc"))?;
+        let acc = Vec3::parse(acc_token).map_err(|e| e.within(line, acc_token))?;
         Ok(Particle::new(pos, vel, acc))
     }
 }
@@ -124,7 +144,8 @@ impl fmt::Display for Particle {
 }
 
 
-struct ParticleSystem {
+#[derive(Clone)]
+pub struct ParticleSystem {
     particles: Vec<Particle>,
 }
 
@@ -133,22 +154,15 @@ impl ParticleSystem {
         ParticleSystem { particles: Vec::new()}
     }
 
-    fn from_str(s: &str) -> Result<ParticleSystem, Box<Error>> {
+    fn from_str(s: &str) -> Result<ParticleSystem, Error> {
         let mut system = ParticleSystem::new();
-        for l in s.split('\n').map(|l| l.trim()).filter(|l| !l.is_empty()) {
-            let p = Particle::parse(l)?;
+        for (idx, l) in s.split('\n').enumerate().filter(|&(_, l)| !l.trim().is_empty()) {
+            let p = Particle::parse(l).map_err(|e| e.on_line(idx + 1))?;
             system.particles.push(p)
         }
         Ok(system)
     }
 
-    fn from_file(path: &str) -> Result<ParticleSystem, Box<Error>> {
-        let mut file = File::open(path)?;
-        let mut buff = String::new();
-        file.read_to_string(&mut buff)?;
-        ParticleSystem::from_str(&buff)
-    }
-
     fn update(&mut self) {
         for p in self.particles.iter_mut() {
             p.vel.add(&p.acc);
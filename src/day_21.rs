@@ -1,10 +1,8 @@
 use std::ops::Index;
 use std::ops::IndexMut;
-use std::error::Error;
-use simple_error::SimpleError;
 use std::fmt;
-use std::fs::File;
-use std::io::prelude::*;
+use error::Error;
+use solution::Solution;
 
 macro_rules! pattern 
 {
@@ -12,11 +10,7 @@ macro_rules! pattern
         {
             let mut pattern = Pattern{ size: 0, data: Vec::new()};
             $(
-                #[allow(unused_mut)]
-                let mut row = Vec::new();
-                $(
-                    row.push($x);
-                )*
+                let row: Vec<char> = vec![$($x),*];
                 pattern.size = row.len();
                 pattern.data.extend(row.iter());
 
@@ -26,33 +20,52 @@ macro_rules! pattern
     };
 }
 
-pub fn first_puzzle() -> String {
-    let init_patt = pattern!['.', '#', '.';
-                            '.', '.', '#';
-                            '#', '#', '#'];
-    
+pub struct Day21;
 
-    let rules = Rule::from_file("enchancment.txt").unwrap();
-    let mut curr_pattern = init_patt;
-    for _ in 0 .. 5 {
-        curr_pattern = Pattern::from_iter(curr_pattern.iter(), &rules);
+impl Solution for Day21 {
+    type Input = Vec<Rule>;
+
+    fn day(&self) -> u32 {
+        21
     }
 
-    format!("{}", curr_pattern.count_ones())
-}
+    fn name(&self) -> &'static str {
+        "Fractal Art"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "enchancment.txt"
+    }
 
-pub fn second_puzzle() -> String {
-    let init_patt = pattern!['.', '#', '.';
-                            '.', '.', '#';
-                            '#', '#', '#'];
-    
-    let rules = Rule::from_file("enchancment.txt").unwrap();
-    let mut curr_pattern = init_patt;
-    for _ in 0 .. 18 {
-        curr_pattern = Pattern::from_iter(curr_pattern.iter(), &rules);
+    fn parse(&self, input: &str) -> Result<Vec<Rule>, Error> {
+        Rule::from_str(input)
     }
 
-    format!("{}", curr_pattern.count_ones())
+    fn first_puzzle(&self, rules: &Vec<Rule>) -> Result<String, Error> {
+        let init_patt = pattern!['.', '#', '.';
+                                '.', '.', '#';
+                                '#', '#', '#'];
+
+        let mut curr_pattern = init_patt;
+        for _ in 0 .. 5 {
+            curr_pattern = Pattern::from_iter(curr_pattern.iter()?, rules)?;
+        }
+
+        Ok(format!("{}", curr_pattern.count_ones()))
+    }
+
+    fn second_puzzle(&self, rules: &Vec<Rule>) -> Result<String, Error> {
+        let init_patt = pattern!['.', '#', '.';
+                                '.', '.', '#';
+                                '#', '#', '#'];
+
+        let mut curr_pattern = init_patt;
+        for _ in 0 .. 18 {
+            curr_pattern = Pattern::from_iter(curr_pattern.iter()?, rules)?;
+        }
+
+        Ok(format!("{}", curr_pattern.count_ones()))
+    }
 }
 
 
@@ -137,57 +150,58 @@ impl Pattern {
         trans
     }
 
-    fn enchance(&self, rules: &Vec<Rule>) -> Pattern {
+    fn enchance(&self, rules: &Vec<Rule>) -> Result<Pattern, Error> {
         for rule in rules {
             if rule.matches(self) {
-                return rule.ench_pattern.clone()
+                return Ok(rule.ench_pattern.clone())
             }
         }
-        panic!("Failed to match pattern with given rules: {}", self);
+        Err(Error::no_solution(format!("failed to match pattern with given rules: {}", self)))
     }
 
-    fn next_size(size: usize) -> usize {
-        if size % 2 == 0 {
-            size / 2 * 3
+    fn next_size(size: usize) -> Result<usize, Error> {
+        if size.is_multiple_of(2) {
+            Ok(size / 2 * 3)
         }
-        else if size % 3 == 0 {
-            size / 3 * 4
+        else if size.is_multiple_of(3) {
+            Ok(size / 3 * 4)
         }
         else {
-            panic!("invalid fractal size: {}", size);
+            Err(Error::invalid_state(format!("invalid fractal size: {}", size)))
         }
     }
 
-    fn step(&self) -> usize {
-        if self.size % 2 == 0 {
-            2
+    fn step(&self) -> Result<usize, Error> {
+        if self.size.is_multiple_of(2) {
+            Ok(2)
         }
-        else if self.size % 3 == 0 {
-            3
+        else if self.size.is_multiple_of(3) {
+            Ok(3)
         }
         else {
-            panic!("invalid pattern size: {}", self.size);
+            Err(Error::invalid_state(format!("invalid pattern size: {}", self.size)))
         }
     }
 
-    fn iter<'a>(&'a self) -> PatternIterator<'a> {
+    fn iter<'a>(&'a self) -> Result<PatternIterator<'a>, Error> {
         PatternIterator::new(self)
     }
 
-    fn from_iter(mut iter: PatternIterator, rules: &Vec<Rule>) -> Pattern {
-        let next_size = Pattern::next_size(iter.pattern.size);
+    fn from_iter(mut iter: PatternIterator, rules: &Vec<Rule>) -> Result<Pattern, Error> {
+        let next_size = Pattern::next_size(iter.pattern.size)?;
         let mut next_pattern = Pattern::zeros(next_size);
 
         let mut row = 0;
         let mut col = 0;
         while let Some(pattern) = iter.next().map(|p| p.enchance(rules)) {
+            let pattern = pattern?;
             for i in 0 .. pattern.size {
                 let next_pattern_range = (row + i) * next_pattern.size + col .. (row + i) * next_pattern.size + col + pattern.size;
                 next_pattern.data.splice(next_pattern_range, pattern.data.iter().skip(i * pattern.size).take(pattern.size).cloned());
             }
 
             if row == next_pattern.size {
-                panic!("too many patterns"); 
+                return Err(Error::invalid_state("too many patterns"));
             }
             else if col < next_pattern.size - pattern.size {
                 col += pattern.size;
@@ -197,7 +211,7 @@ impl Pattern {
                 col = 0;
             }
         }
-      next_pattern
+      Ok(next_pattern)
     }
 
     fn count_ones(&self) -> usize {
@@ -213,7 +227,7 @@ impl fmt::Display for Pattern {
             for c in 0 .. self.size {
                 write!(f, "{}", self.data[r * self.size + c])?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         write!(f, "")
     }
@@ -233,34 +247,39 @@ impl IndexMut<(usize, usize)> for Pattern {
     }
 }
 
-struct Rule {
+pub struct Rule {
     match_pattern: Pattern,
     ench_pattern: Pattern
 }
 
 impl Rule {
-    fn parse(line: &str) -> Result<Rule, Box<Error>> {
+    fn parse(line: &str) -> Result<Rule, Error> {
         let mut iter  = line.split("=>").map(|token| token.trim());
-        let match_pattern_token = iter.next().ok_or(SimpleError::new(&format!("missing input pattern ({})", line)))?;
-        let match_pattern = Rule::parse_pattern(match_pattern_token)?;
-        let ench_pattern_token = iter.next().ok_or(SimpleError::new(&format!("missing output pattern ({})", line)))?;
-        let ench_pattern = Rule::parse_pattern(ench_pattern_token)?;
+        let match_pattern_token = iter.next().ok_or_else(|| Error::parse(1, 1, format!("missing input pattern ({})", line)))?;
+        let match_pattern = Rule::parse_pattern(match_pattern_token).map_err(|e| e.within(line, match_pattern_token))?;
+        let ench_pattern_token = iter.next().ok_or_else(|| Error::parse(1, line.len() + 1, format!("missing output pattern ({})", line)))?;
+        let ench_pattern = Rule::parse_pattern(ench_pattern_token).map_err(|e| e.within(line, ench_pattern_token))?;
+
+        if ench_pattern.size != match_pattern.size + 1 {
+            return Err(Error::at_token(1, line, ench_pattern_token, format!("output pattern should have size {}", match_pattern.size + 1)));
+        }
 
         Ok(Rule {match_pattern, ench_pattern})
     }
 
-    fn parse_pattern(pattern: &str) -> Result<Pattern, Box<Error>> {
+    fn parse_pattern(pattern: &str) -> Result<Pattern, Error> {
        let size  = pattern.matches('/').count() + 1;
        let mut mat = Pattern::zeros(size);
-       let mut iter = pattern.split('/').map(|token| token.trim());
-       for r in 0 .. size {
-           let row_token = iter.next().ok_or(SimpleError::new(&format!("missing row {} ({})", r, pattern)))?;
+       for (r, row_token) in pattern.split('/').map(|token| token.trim()).enumerate() {
+           if row_token.chars().count() != size {
+               return Err(Error::at_token(1, pattern, row_token, format!("row {} should have {} cells ({})", r, size, pattern)));
+           }
+           if let Some((idx, ch)) = row_token.char_indices().find(|&(_, ch)| ch != '.' && ch != '#') {
+               return Err(Error::at_token(1, pattern, &row_token[idx ..], format!("invalid char: {}", ch)));
+           }
+
            let range = (r * size) .. (r + 1) * size;
-           mat.data.splice(range, row_token.chars()
-                                           .map(|ch| match ch {
-                                                   '.' | '#' => ch,
-                                                   _ => panic!("invalid char: {}", ch)
-                                           })).collect::<Vec<_>>();
+           mat.data.splice(range, row_token.chars());
        }
 
        Ok(mat)
@@ -277,13 +296,10 @@ impl Rule {
         false
     }
 
-    fn from_file(path: &str) -> Result<Vec<Rule>, Box<Error>> {
-        let mut f = File::open(path)?;
-        let mut buff = String::new();
-        f.read_to_string(&mut buff)?;
+    fn from_str(rules: &str) -> Result<Vec<Rule>, Error> {
         let mut v = Vec::new();
-        for l in buff.split('\n').filter(|l| !l.is_empty()) {
-            let r = Rule::parse(l)?;
+        for (idx, l) in rules.split('\n').enumerate().filter(|&(_, l)| !l.trim().is_empty()) {
+            let r = Rule::parse(l).map_err(|e| e.on_line(idx + 1))?;
             v.push(r);
         }
 
@@ -294,17 +310,18 @@ impl Rule {
 struct PatternIterator<'a> {
     row: usize,
     col: usize,
+    step: usize,
     pattern: &'a Pattern
 }
 
 impl<'a> PatternIterator<'a> {
-    fn new(pattern: &'a Pattern) -> PatternIterator {
-        PatternIterator {row: 0, col: 0, pattern}
+    fn new(pattern: &'a Pattern) -> Result<PatternIterator<'a>, Error> {
+        Ok(PatternIterator {row: 0, col: 0, step: pattern.step()?, pattern})
     }
 
     fn pattern(&mut self) -> Pattern {
        let mut data = Vec::new(); 
-       let step = self.pattern.step();
+       let step = self.step;
        for r in self.row .. self.row + step {
            data.extend(self.pattern.data.iter()
                                    .skip(r * self.pattern.size + self.col)
@@ -324,11 +341,11 @@ impl<'a> Iterator for PatternIterator<'a> {
         }
 
         let pattern = self.pattern();
-        if self.col < self.pattern.size - self.pattern.step() {
-            self.col += self.pattern.step();
+        if self.col < self.pattern.size - self.step {
+            self.col += self.step;
         }
         else {
-            self.row += self.pattern.step();
+            self.row += self.step;
             self.col = 0;
         }
 
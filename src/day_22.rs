@@ -1,81 +1,59 @@
 use std::collections::HashMap;
 use std::fmt;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let grid = Grid::initialize(
-".########.....#...##.####
-....#..#.#.##.###..#.##..
-##.#.#..#.###.####.##.#..
-####...#...####...#.##.##
-..#...###.#####.....##.##
-..#.##.######.#...###...#
-.#....###..##....##...##.
-##.##..####.#.######...##
-#...#..##.....#..#...#..#
-........#.##..###.#.....#
-#.#..######.#.###..#...#.
-.#.##.##..##.####.....##.
-.....##..#....#####.#.#..
-...#.#.#..####.#..###..#.
-##.#..##..##....#####.#..
-.#.#..##...#.#####....##.
-.####.#.###.####...#####.
-...#...######..#.##...#.#
-#..######...#.####.#..#.#
-...##..##.#.##.#.#.#....#
-###..###.#..#.....#.##.##
-..#....##...#..#..##..#..
-.#.###.##.....#.###.#.###
-####.##...#.#....#..##...
-#.....#.#..#.##.#..###..#");
-
-    let mut carrier = VirusCarier::new(grid);
-    for _ in 0..10000 {
-        carrier.burst();
-    }
-    format!("{}", carrier.infections)
-}
+pub struct Day22;
+
+impl Solution for Day22 {
+    type Input = Grid;
+
+    fn day(&self) -> u32 {
+        22
+    }
+
+    fn name(&self) -> &'static str {
+        "Sporifica Virus"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "infection.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Grid, Error> {
+        let map = input.trim();
+        for (row, line) in map.split('\n').enumerate() {
+            if let Some(col) = line.trim().find(|ch| ch != '#' && ch != '.') {
+                let column = col + line.len() - line.trim_start().len() + 1;
+                return Err(Error::parse(row + 1, column, format!("invalid node: {}", line.trim())));
+            }
+        }
+        Ok(Grid::initialize(map))
+    }
 
-pub fn second_puzzle() -> String {
-   let grid = Grid::initialize(
-".########.....#...##.####
-....#..#.#.##.###..#.##..
-##.#.#..#.###.####.##.#..
-####...#...####...#.##.##
-..#...###.#####.....##.##
-..#.##.######.#...###...#
-.#....###..##....##...##.
-##.##..####.#.######...##
-#...#..##.....#..#...#..#
-........#.##..###.#.....#
-#.#..######.#.###..#...#.
-.#.##.##..##.####.....##.
-.....##..#....#####.#.#..
-...#.#.#..####.#..###..#.
-##.#..##..##....#####.#..
-.#.#..##...#.#####....##.
-.####.#.###.####...#####.
-...#...######..#.##...#.#
-#..######...#.####.#..#.#
-...##..##.#.##.#.#.#....#
-###..###.#..#.....#.##.##
-..#....##...#..#..##..#..
-.#.###.##.....#.###.#.###
-####.##...#.#....#..##...
-#.....#.#..#.##.#..###..#");
-
-    let mut carrier = VirusCarier::new(grid);
-    for _ in 0..10000000 {
-        carrier.burst_evolved();
-    }
-    format!("{}", carrier.infections)
+    fn first_puzzle(&self, grid: &Grid) -> Result<String, Error> {
+        let mut carrier = VirusCarier::new(grid.clone());
+        for _ in 0..10000 {
+            carrier.burst();
+        }
+        Ok(format!("{}", carrier.infections))
+    }
+
+    fn second_puzzle(&self, grid: &Grid) -> Result<String, Error> {
+        let mut carrier = VirusCarier::new(grid.clone());
+        for _ in 0..10000000 {
+            carrier.burst_evolved();
+        }
+        Ok(format!("{}", carrier.infections))
+    }
 }
 
 enum Direction {Top, Bottom, Left, Right}
 #[derive(Clone)]
 enum Node {Clean, Weakened, Infected, Flagged}
 
-struct Grid {
+#[derive(Clone)]
+pub struct Grid {
     infected: HashMap<(i64, i64), Node>,
 }
 
@@ -93,10 +71,9 @@ impl Grid {
         for (row, line) in map.split('\n').map(|l| l.trim()).enumerate() {
            for (col, ch) in line.chars().enumerate() {
                let pos = (col as i64 - offset, offset - row as i64 );
-               match ch {
-                   '#' => {grid.set(&pos, Node::Infected);},
-                   _ => {}
-               };
+               if ch == '#' {
+                   grid.set(&pos, Node::Infected);
+               }
            } 
         }
         grid
@@ -114,17 +91,17 @@ impl Grid {
     fn set(&mut self, pos: &(i64, i64), node: Node) {
         match node {
             Node::Clean => {self.infected.remove(pos);}
-            _ => {self.infected.insert(pos.clone(), node);}
+            _ => {self.infected.insert(*pos, node);}
         }
     }
 }
 
 impl fmt::Display for Grid {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut min_x = i64::max_value();
-        let mut max_x = i64::min_value();
-        let mut min_y = i64::max_value();
-        let mut max_y = i64::min_value();
+        let mut min_x = i64::MAX;
+        let mut max_x = i64::MIN;
+        let mut min_y = i64::MAX;
+        let mut max_y = i64::MIN;
 
         for (&(x,y), _) in self.infected.iter() {
             min_x = i64::min(min_x, x);
@@ -149,7 +126,7 @@ impl fmt::Display for Grid {
                     Node::Infected => write!(f, "#")?
                 }
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
         write!(f, "")
@@ -166,7 +143,7 @@ struct VirusCarier {
 
 impl VirusCarier {
     fn new(grid: Grid) -> VirusCarier {
-        VirusCarier {grid: grid, pos: (0,0), dir: Direction::Top, infections: 0}
+        VirusCarier {grid, pos: (0,0), dir: Direction::Top, infections: 0}
     }
 
     fn burst(&mut self) {
@@ -1,45 +1,208 @@
+use std::collections::HashMap;
 use day_18::*;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let mut assembler = Assembler::new(0);
-    assembler.load_program("coprocessor.txt");
-    let mut mul_counter = 0;
-    while !assembler.terminated() {
-        if assembler.instructions[assembler.ic as usize].starts_with("mul") {
-            mul_counter += 1;
-        }
-        assembler.execute_next_instruction();
-    }
+pub struct Day23;
+
+/// Main loop counting the composite numbers in `b ..= c`, uppercase letters stand for registers.
+const COMPOSITE_LOOP: [&str; 24] = [
+    "set F 1", "set D 2", "set E 2", "set G D", "mul G E", "sub G B", "jnz G 2", "set F 0",
+    "sub E -1", "set G E", "sub G B", "jnz G -8", "sub D -1", "set G D", "sub G B", "jnz G -13",
+    "jnz F 2", "sub H -1", "set G B", "sub G C", "jnz G 2", "jnz 1 3", "sub B STEP", "jnz 1 -23",
+];
 
-    format!("{}", mul_counter)
+/// Registers bound to the placeholders of `COMPOSITE_LOOP` and the step added to `b`.
+struct CompositeLoop {
+    registers: HashMap<&'static str, Register>,
+    step: i64,
 }
 
+/// Operands of `instruction` in source order, a target register first.
+fn operands(instruction: &Instruction) -> Vec<Operand> {
+    match *instruction {
+        Instruction::Snd(x) => vec![x],
+        Instruction::Rcv(r) => vec![Operand::Reg(r)],
+        Instruction::Set(r, y) | Instruction::Add(r, y) | Instruction::Sub(r, y) | Instruction::Mul(r, y) | Instruction::Mod(r, y) => {
+            vec![Operand::Reg(r), y]
+        },
+        Instruction::Jgz(x, y) | Instruction::Jnz(x, y) => vec![x, y],
+    }
+}
 
-pub fn second_puzzle() -> String {
-    let mut b = 108100;
-    let c = 125100;
-    let mut h = 0;
+impl CompositeLoop {
+    fn match_body(body: &[Instruction]) -> Option<CompositeLoop> {
+        if body.len() != COMPOSITE_LOOP.len() {
+            return None;
+        }
 
-    loop {
-        if !is_prime(b){
-            h += 1 
+        let mut registers = HashMap::new();
+        let mut step = None;
+        for (instruction, pattern) in body.iter().zip(COMPOSITE_LOOP.iter()) {
+            let mut expected = pattern.split_whitespace();
+            if expected.next() != Some(instruction.opcode()) {
+                return None;
+            }
+            let expected = expected.collect::<Vec<_>>();
+            let operands = operands(instruction);
+            if operands.len() != expected.len() {
+                return None;
+            }
+
+            for (operand, expected) in operands.into_iter().zip(expected) {
+                match (operand, expected) {
+                    (Operand::Const(c), "STEP") => step = Some(c.checked_neg().filter(|&step| step > 0)?),
+                    (Operand::Reg(reg), name) if name != "STEP" && name.chars().all(|c| c.is_ascii_uppercase()) => {
+                        if *registers.entry(name).or_insert(reg) != reg {
+                            return None;
+                        }
+                    },
+                    (Operand::Const(c), literal) if literal.parse::<i64>() == Ok(c) => (),
+                    _ => return None,
+                }
+            }
+        }
+
+        let mut distinct = registers.values().collect::<Vec<_>>();
+        distinct.sort();
+        distinct.dedup();
+        if distinct.len() != registers.len() {
+            return None;
         }
-        if b == c {
-            break 
+        step.map(|step| CompositeLoop { registers, step })
+    }
+}
+
+impl Solution for Day23 {
+    type Input = Vec<Instruction>;
+
+    fn day(&self) -> u32 {
+        23
+    }
+
+    fn name(&self) -> &'static str {
+        "Coprocessor Conflagration"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "coprocessor.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<Instruction>, Error> {
+        Ok(assemble_dialect(input, Dialect::Coprocessor)?)
+    }
+
+    fn first_puzzle(&self, program: &Vec<Instruction>) -> Result<String, Error> {
+        let mut assembler = Assembler::with_program(0, Dialect::Coprocessor, program)?;
+        let mut mul_counter = 0;
+        while let Some(instruction) = assembler.current() {
+            if let Instruction::Mul(..) = instruction {
+                mul_counter += 1;
+            }
+            assembler.execute_next_instruction()?;
         }
-        b += 17 
+
+        Ok(format!("{}", mul_counter))
     }
 
-    format!("{}", h)
+    fn second_puzzle(&self, program: &Vec<Instruction>) -> Result<String, Error> {
+        let unsupported = |reason: &str| Error::no_solution(format!("unsupported program shape: {}", reason));
+
+        let (end, header) = ControlFlowGraph::new(program).back_edges()
+                                                          .into_iter()
+                                                          .max_by_key(|&(from, to)| (from - to, from))
+                                                          .ok_or_else(|| unsupported("no loop"))?;
+        let main_loop = CompositeLoop::match_body(&program[header ..= end])
+                                      .ok_or_else(|| unsupported("main loop does not count composite numbers"))?;
+
+        let mut assembler = Assembler::with_program(0, Dialect::Coprocessor, program)?;
+        assembler.registers[register('a').unwrap_or(0)] = 1;
+        let mut steps = 0;
+        while assembler.ic != header as i64 {
+            if assembler.terminated() || steps == 100_000 {
+                return Err(unsupported("preamble does not reach the main loop"));
+            }
+            assembler.execute_next_instruction()?;
+            steps += 1;
+        }
+
+        let mut b = assembler.registers[main_loop.registers["B"]];
+        let c = assembler.registers[main_loop.registers["C"]];
+        let mut h = assembler.registers[main_loop.registers["H"]];
+        if b < 3 || c < b || (c - b) % main_loop.step != 0 {
+            return Err(unsupported(&format!("b = {} never reaches c = {}", b, c)));
+        }
+
+        loop {
+            if !is_prime(b){
+                h += 1 
+            }
+            if b == c {
+                break 
+            }
+            b += main_loop.step
+        }
+
+        Ok(format!("{}", h))
+    }
 }
 
 fn is_prime(n: i64) -> bool {
-    let sqrt = f64::sqrt(n as f64) as i64;
-    for i in 2 .. sqrt {
+    if n < 2 {
+        return false
+    }
+    // `i * i <= n` written so that it cannot overflow near `i64::MAX`.
+    let mut i = 2;
+    while i <= n / i {
         if n % i == 0 {
             return false
         }
+        i += 1;
     }
 
     true
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn composite_program(preamble: &str, step: i64) -> Vec<Instruction> {
+        let main_loop = COMPOSITE_LOOP.join("\n")
+                                      .replace("STEP", &(-step).to_string())
+                                      .to_lowercase();
+        assemble_dialect(&format!("{}\n{}", preamble, main_loop), Dialect::Coprocessor).unwrap()
+    }
+
+    #[test]
+    fn test_is_prime() {
+        assert!([2, 3, 5, 13, 97].iter().all(|&n| is_prime(n)));
+        assert!(![4, 9, 25, 49, 10_201].iter().any(|&n| is_prime(n)));
+        assert!(![i64::MIN, -7, 0, 1].iter().any(|&n| is_prime(n)));
+        assert!(!is_prime(i64::MAX));
+    }
+
+    #[test]
+    fn second_puzzle() {
+        let program = composite_program("set b 3\nset c 12\njnz a 2\njnz 1 2\nsub h -1", 1);
+        let mut assembler = Assembler::with_program(0, Dialect::Coprocessor, &program).unwrap();
+        assembler.registers[register('a').unwrap()] = 1;
+        assembler.run(1_000_000).unwrap();
+        assert!(assembler.terminated());
+        assert_eq!(assembler.register('h'), 7);
+        assert_eq!(Day23.second_puzzle(&program).unwrap(), "7");
+
+        assert_eq!(Day23.second_puzzle(&composite_program("set b 3\nset c 13", 2)).unwrap(), "1");
+        assert_eq!(Day23.second_puzzle(&composite_program("set b 3\nset c 12", 2)).unwrap_err().to_string(),
+                   "no solution: unsupported program shape: b = 3 never reaches c = 12");
+
+        let mut altered = composite_program("set b 3\nset c 12", 1);
+        altered[5] = Instruction::Mul(6, Operand::Reg(3));
+        assert_eq!(Day23.second_puzzle(&altered).unwrap_err().to_string(),
+                   "no solution: unsupported program shape: main loop does not count composite numbers");
+
+        let mut altered = composite_program("set b 3\nset c 12", 1);
+        altered[24] = Instruction::Sub(1, Operand::Const(i64::MIN));
+        assert_eq!(Day23.second_puzzle(&altered).unwrap_err().to_string(),
+                   "no solution: unsupported program shape: main loop does not count composite numbers");
+    }
+}
@@ -1,21 +1,39 @@
-use std::fs::File;
-use std::io::prelude::*;
-use simple_error::SimpleError;
-use std::error::Error;
 use std::collections::HashSet;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let graph = Graph::from_file("bridge.txt").unwrap();
-    format!("{}", graph.find_max_strength(0))
-}
+pub struct Day24;
+
+impl Solution for Day24 {
+    type Input = Graph;
+
+    fn day(&self) -> u32 {
+        24
+    }
+
+    fn name(&self) -> &'static str {
+        "Electromagnetic Moat"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "bridge.txt"
+    }
 
-pub fn second_puzzle() -> String {
-    let graph = Graph::from_file("bridge.txt").unwrap();
-    let max_length = graph.find_max_length(0);
-    format!("{}", graph.find_max_strength_with_length(0, max_length))
+    fn parse(&self, input: &str) -> Result<Graph, Error> {
+        Graph::from_str(input)
+    }
+
+    fn first_puzzle(&self, graph: &Graph) -> Result<String, Error> {
+        Ok(format!("{}", graph.find_max_strength(0)))
+    }
+
+    fn second_puzzle(&self, graph: &Graph) -> Result<String, Error> {
+        let max_length = graph.find_max_length(0);
+        Ok(format!("{}", graph.find_max_strength_with_length(0, max_length)))
+    }
 }
 
-struct Graph {
+pub struct Graph {
     edges: Vec<(u64, u64)>
 }
 
@@ -26,30 +44,25 @@ impl Graph {
         }
     }
 
-    fn from_str(lines: &str) -> Result<Graph, Box<Error>> {
+    fn from_str(lines: &str) -> Result<Graph, Error> {
         let mut graph = Graph::new();
-        for line in lines.split('\n').map(|l| l.trim()).filter(|l| !l.is_empty()) {
-            graph.parse(line)?;
+        for (idx, line) in lines.split('\n').enumerate().filter(|&(_, l)| !l.trim().is_empty()) {
+            graph.parse(line).map_err(|e| e.on_line(idx + 1))?;
         }
         Ok(graph)
     }
 
-    fn from_file(path: &str) -> Result<Graph, Box<Error>> {
-        let mut file = File::open(path)?;
-        let mut buffer = String::new();
-        file.read_to_string(&mut buffer)?;
-        Graph::from_str(&buffer)
-    }
-
-    fn parse(&mut self, line: &str) -> Result<(), Box<Error>> {
+    fn parse(&mut self, line: &str) -> Result<(), Error> {
+        let port = |token: &str| token.trim().parse::<u64>()
+                                      .map_err(|e| Error::at_token(1, line, token, format!("invalid port {}: {}", token, e)));
         let mut iter = line.split('/');
         let first_token = iter.next()
-                              .ok_or(SimpleError::new(&format!("missing first port: {}", line)))?;
-        let first_node = first_token.parse::<u64>()?;
+                              .ok_or_else(|| Error::parse(1, 1, format!("missing first port: {}", line)))?;
+        let first_node = port(first_token)?;
 
         let second_token = iter.next()
-                               .ok_or(SimpleError::new(&format!("missing second port: {}", line)))?;
-        let second_node = second_token.parse::<u64>()?;
+                               .ok_or_else(|| Error::parse(1, line.len() + 1, format!("missing second port: {}", line)))?;
+        let second_node = port(second_token)?;
         self.edges.push((first_node, second_node));
         Ok(())
     }
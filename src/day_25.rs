@@ -1,194 +1,309 @@
-use std::collections::HashSet;
-use std::collections::HashMap;
 use std::fmt;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String {
-    let mut tape = Tape::new();
-        tape.insert('A', Box::new(|tape: &mut Tape | -> char {
-            if !tape.get() {
-                tape.set(true);
-                tape.move_right();
-                'B'
-            }
-            else {
-                tape.set(false);
-                tape.move_left();
-                'C'
-            }
-        }));
+pub struct Day25;
 
-        tape.insert('B', Box::new(|tape: &mut Tape | -> char {
-            if !tape.get() {
-                tape.set(true);
-                tape.move_left();
-                'A'
-            }
-            else {
-                tape.set(true);
-                tape.move_right();
-                'D'
-            }
-        }));
+impl Solution for Day25 {
+    type Input = Blueprint;
 
-        tape.insert('C', Box::new(|tape: &mut Tape | -> char {
-            if !tape.get() {
-                tape.set(false);
-                tape.move_left();
-                'B'
-            }
-            else {
-                tape.set(false);
-                tape.move_left();
-                'E'
+    fn day(&self) -> u32 {
+        25
+    }
+
+    fn name(&self) -> &'static str {
+        "The Halting Problem"
+    }
+
+    fn input_path(&self) -> &'static str {
+        "states.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Blueprint, Error> {
+        Blueprint::parse(input)
+    }
+
+    fn first_puzzle(&self, blueprint: &Blueprint) -> Result<String, Error> {
+        let mut tape = Tape::from_blueprint(blueprint);
+        if !tape.select(blueprint.start) {
+            return Err(Error::invalid_state(format!("unknown start state: {}", blueprint.start)));
+        }
+        for _ in 0 .. blueprint.steps {
+            tape.execute()?;
+        }
+
+        Ok(format!("{}", tape.checksum()))
+    }
+
+    fn second_puzzle(&self, _: &Blueprint) -> Result<String, Error> {
+        Ok(String::new())
+    }
+}
+
+
+type StateId = char;
+type Symbol = u8;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Move { Left, Right }
+
+/// What a state does after reading a single symbol.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Rule {
+    write: Symbol,
+    dir: Move,
+    next: StateId,
+}
+
+/// Turing machine description in the puzzle prose format.
+#[derive(Debug)]
+pub struct Blueprint {
+    start: StateId,
+    steps: u64,
+    /// Rules of each state in definition order, indexed by the symbol read.
+    states: Vec<(StateId, Vec<Option<Rule>>)>,
+}
+
+impl Blueprint {
+    fn parse(input: &str) -> Result<Blueprint, Error> {
+        let mut start = None;
+        let mut steps = None;
+        let mut partial: Vec<(StateId, usize, Vec<Option<PartialRule>>)> = Vec::new();
+        let mut value = None;
+
+        for (idx, line) in input.lines().enumerate() {
+            let line_no = idx + 1;
+            let trimmed = line.trim().trim_start_matches("- ");
+            if trimmed.is_empty() {
+                continue;
             }
-        }));
+            let word = trimmed.split_whitespace()
+                              .last()
+                              .map(|w| w.trim_end_matches(['.', ':']))
+                              .unwrap_or(trimmed);
+            let invalid = |what: &str| Error::at_token(line_no, line, word, format!("invalid {}: {}", what, word));
+            let state_id = || match (word.chars().next(), word.chars().nth(1)) {
+                (Some(id), None) => Ok(id),
+                _ => Err(invalid("state name"))
+            };
+            let symbol = || word.parse::<Symbol>().map_err(|_| invalid("value"));
 
-        tape.insert('D', Box::new(|tape: &mut Tape | -> char {
-            if !tape.get() {
-                tape.set(true);
-                tape.move_right();
-                'A'
+            if trimmed.starts_with("Begin in state") {
+                start = Some((state_id()?, line_no));
             }
-            else {
-                tape.set(false);
-                tape.move_right();
-                'B'
+            else if trimmed.starts_with("Perform a diagnostic checksum after") {
+                let token = trimmed.split_whitespace().nth(5).unwrap_or(trimmed);
+                steps = Some(token.parse().map_err(|e| Error::at_token(line_no, line, token, format!("invalid steps {}: {}", token, e)))?);
             }
-        }));
-        tape.insert('E', Box::new(|tape: &mut Tape | -> char {
-            if !tape.get() {
-                tape.set(true);
-                tape.move_left();
-                'F'
+            else if trimmed.starts_with("In state") {
+                let id = state_id()?;
+                if partial.iter().any(|&(other, _, _)| other == id) {
+                    return Err(Error::at_token(line_no, line, word, format!("state {} is defined twice", id)));
+                }
+                partial.push((id, line_no, Vec::new()));
+                value = None;
+            }
+            else if trimmed.starts_with("If the current value is") {
+                let read = symbol()? as usize;
+                let rules = match partial.last_mut() {
+                    Some(&mut (_, _, ref mut rules)) => rules,
+                    None => return Err(Error::parse(line_no, 1, format!("condition outside of a state: {}", trimmed))),
+                };
+                if rules.len() <= read {
+                    rules.resize(read + 1, None);
+                }
+                if rules[read].is_some() {
+                    return Err(Error::at_token(line_no, line, word, format!("value {} is handled twice", read)));
+                }
+                rules[read] = Some(PartialRule::default());
+                value = Some(read);
             }
             else {
-                tape.set(true);
-                tape.move_left();
-                'C'
+                let rule = match (partial.last_mut(), value) {
+                    (Some(&mut (_, _, ref mut rules)), Some(value)) => &mut rules[value],
+                    _ => return Err(Error::parse(line_no, 1, format!("rule outside of a state condition: {}", trimmed))),
+                };
+                let rule = match rule {
+                    Some(rule) => rule,
+                    None => return Err(Error::parse(line_no, 1, format!("rule outside of a state condition: {}", trimmed))),
+                };
+
+                if trimmed.starts_with("Write the value") {
+                    rule.write = Some(symbol()?);
+                }
+                else if trimmed.starts_with("Move one slot to the") {
+                    rule.dir = match word {
+                        "left" => Some(Move::Left),
+                        "right" => Some(Move::Right),
+                        _ => return Err(invalid("direction")),
+                    };
+                }
+                else if trimmed.starts_with("Continue with state") {
+                    rule.next = Some((state_id()?, line_no));
+                }
+                else {
+                    return Err(Error::parse(line_no, 1, format!("unrecognized line: {}", trimmed)));
+                }
             }
-        }));
-        tape.insert('F', Box::new(|tape: &mut Tape | -> char {
-            if !tape.get() {
-                tape.set(true);
-                tape.move_right();
-                'D'
+        }
+
+        let (start, start_line) = start.ok_or_else(|| Error::parse(1, 1, "missing start state"))?;
+        let steps = steps.ok_or_else(|| Error::parse(2, 1, "missing checksum steps"))?;
+        let known = |id: StateId, line_no: usize| {
+            if partial.iter().any(|&(other, _, _)| other == id) {
+                Ok(id)
             }
             else {
-                tape.set(true);
-                tape.move_right();
-                'A'
+                Err(Error::parse(line_no, 1, format!("unknown state: {}", id)))
             }
-        }));
+        };
 
-        tape.select('A');
-        for _ in 0 .. 12481997 {
-            tape.execute();
+        known(start, start_line)?;
+        let mut states = Vec::new();
+        for &(id, line_no, ref rules) in partial.iter() {
+            let complete = |(value, rule): (usize, &Option<PartialRule>)| match *rule {
+                Some(PartialRule { write: Some(write), dir: Some(dir), next: Some((next, next_line)) }) => {
+                    Ok(Some(Rule { write, dir, next: known(next, next_line)? }))
+                },
+                Some(_) => Err(Error::parse(line_no, 1, format!("state {} has incomplete rule for value {}", id, value))),
+                None => Ok(None),
+            };
+            states.push((id, rules.iter().enumerate().map(complete).collect::<Result<Vec<_>, Error>>()?));
         }
 
-    format!("{}", tape.checksum()) 
+        Ok(Blueprint { start, steps, states })
+    }
 }
 
-pub fn second_puzzle() -> String {
-    format!("") 
+/// Rule fields collected so far while parsing a state.
+#[derive(Default, Clone)]
+struct PartialRule {
+    write: Option<Symbol>,
+    dir: Option<Move>,
+    next: Option<(StateId, usize)>,
 }
 
+/// Entry of the state x symbol transition table.
+#[derive(Clone, Copy)]
+struct Transition {
+    write: Symbol,
+    step: isize,
+    next: usize,
+}
 
-
-type StateId = char;
-type State = Box<Fn(&mut Tape)-> StateId>;
-
+/// Turing machine running a compiled transition table over a bidirectional tape.
 struct Tape {
-    data: HashSet<i64>,
-    pos: i64,
-    cur: Option<StateId>,
-    states: HashMap<StateId, State>
+    cells: Vec<Symbol>,
+    /// Index of the cell the machine started on.
+    origin: usize,
+    pos: usize,
+    non_blank: u64,
+    symbols: usize,
+    names: Vec<StateId>,
+    table: Vec<Option<Transition>>,
+    cur: Option<usize>,
 }
 
 impl Tape {
     fn new() -> Tape {
         Tape {
-            data: HashSet::new(),
-            pos: 0, 
-            cur: None,
-            states: HashMap::new()}
+            cells: vec![0; 64],
+            origin: 32,
+            pos: 32,
+            non_blank: 0,
+            symbols: 0,
+            names: Vec::new(),
+            table: Vec::new(),
+            cur: None}
     }
 
-    fn get(&self) -> bool {
-        self.data.contains(&self.pos)
-    }
+    fn from_blueprint(blueprint: &Blueprint) -> Tape {
+        let mut tape = Tape::new();
+        tape.names = blueprint.states.iter().map(|&(id, _)| id).collect();
+        tape.symbols = blueprint.states.iter().map(|(_, rules)| rules.len()).max().unwrap_or(0).max(1);
+        tape.table = vec![None; tape.names.len() * tape.symbols];
 
-    fn set(&mut self, value: bool) {
-        if value {
-            self.data.insert(self.pos);
-        }
-        else {
-            self.data.remove(&self.pos);
+        for (state, (_, rules)) in blueprint.states.iter().enumerate() {
+            for (symbol, rule) in rules.iter().enumerate() {
+                let next = rule.and_then(|rule| tape.names.iter().position(|&id| id == rule.next));
+                if let (Some(rule), Some(next)) = (rule, next) {
+                    let step = match rule.dir {
+                        Move::Left => -1,
+                        Move::Right => 1,
+                    };
+                    tape.table[state * tape.symbols + symbol] = Some(Transition { write: rule.write, step, next });
+                }
+            }
         }
+        tape
     }
 
-    fn move_left(&mut self) {
-        self.pos -=1;
+    fn get(&self) -> Symbol {
+        self.cells[self.pos]
     }
 
-    fn move_right(&mut self) {
-        self.pos += 1;
+    fn set(&mut self, value: Symbol) {
+        let cell = &mut self.cells[self.pos];
+        match (*cell, value) {
+            (0, 0) => {},
+            (0, _) => self.non_blank += 1,
+            (_, 0) => self.non_blank -= 1,
+            _ => {}
+        }
+        *cell = value;
     }
 
-    fn select(&mut self, id: StateId) -> bool {
-        if self.states.contains_key(&id) {
-            self.cur = Some(id);
-            true
+    fn shift(&mut self, step: isize) {
+        if step < 0 && self.pos == 0 {
+            let grow = self.cells.len();
+            self.cells.splice(0 .. 0, std::iter::repeat_n(0, grow));
+            self.pos += grow;
+            self.origin += grow;
         }
-        else {
-            false
+        else if step > 0 && self.pos + 1 == self.cells.len() {
+            let len = self.cells.len();
+            self.cells.resize(2 * len, 0);
         }
+        self.pos = (self.pos as isize + step) as usize;
     }
 
-    fn insert(&mut self, id: StateId, state: State) -> Option<State> {
-        self.states.insert(id, state)
+    fn select(&mut self, id: StateId) -> bool {
+        self.cur = self.names.iter().position(|&other| other == id);
+        self.cur.is_some()
     }
 
-    fn remove(&mut self, id: StateId) -> Option<State> {
-        self.states.remove(&id)
-    }
+    fn execute(&mut self) -> Result<(), Error> {
+        let state = self.cur.ok_or_else(|| Error::invalid_state("no state selected"))?;
+        let symbol = self.get();
+        let transition = match self.table.get(state * self.symbols + symbol as usize) {
+            Some(&Some(transition)) if (symbol as usize) < self.symbols => transition,
+            _ => return Err(Error::invalid_state(format!("state {} has no rule for value {}", self.names[state], symbol))),
+        };
 
-    fn execute(&mut self) {
-        if let Some(id) = self.cur {
-            if let Some(state) = self.states.remove(&id) {
-                let next = state(self);
-                self.states.insert(id, state);
-                self.select(next);
-            }
-            else {
-                panic!("invalid state selected: {}", id);
-            }
-        }
-        else {
-            panic!("no state selected");
-        }
+        self.set(transition.write);
+        self.shift(transition.step);
+        self.cur = Some(transition.next);
+        Ok(())
     }
 
-    fn checksum(&mut self) -> u64 {
-        self.data.len() as u64
+    /// Number of non-blank cells, the ones of a binary machine.
+    fn checksum(&self) -> u64 {
+        self.non_blank
     }
 }
 
 impl fmt::Display for Tape {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let min_opt = self.data.iter().min();
-        let max_opt = self.data.iter().max();
-        match (min_opt, max_opt) {
-            (Some(&min), Some(&max)) => {
-                let width = i64::max(min.abs(), max);
-                let off = width / 2 + 1;
-                for i in (0 .. width).map(|i| i - off) {
-                    match self.data.get(&i) {
-                        Some(_) => write!(f, "1 ")?,
-                        _ => write!(f, "0 ")?,
-                    }
-                }
-            },
-            _ => write!(f, "0")?
+        let used = |idx: &usize| self.cells[*idx] != 0 || *idx == self.pos || *idx == self.origin;
+        let first = (0 .. self.cells.len()).find(used).unwrap_or(self.pos);
+        let last = (0 .. self.cells.len()).rev().find(used).unwrap_or(self.pos);
+        for idx in first ..= last {
+            if idx == self.pos {
+                write!(f, "[{}]", self.cells[idx])?;
+            }
+            else {
+                write!(f, " {} ", self.cells[idx])?;
+            }
         }
         write!(f, "")
     }
@@ -199,39 +314,94 @@ impl fmt::Display for Tape {
 mod tests {
     use super::*;
 
+    static BLUEPRINT: &str = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.
+";
+
     #[test]
-    fn first_puzzle() {
-        let mut tape = Tape::new();
-        tape.insert('A', Box::new(|tape: &mut Tape | -> char {
-            if tape.get() {
-                tape.set(false);
-                tape.move_left();
-            }
-            else {
-                tape.set(true);
-                tape.move_right();
-            }
-            'B'
-        }));
+    fn test_blueprint() {
+        let blueprint = Blueprint::parse(BLUEPRINT).unwrap();
+        assert_eq!(blueprint.start, 'A');
+        assert_eq!(blueprint.steps, 6);
+        assert_eq!(blueprint.states[0], ('A', vec![Some(Rule { write: 1, dir: Move::Right, next: 'B' }),
+                                                   Some(Rule { write: 0, dir: Move::Left, next: 'B' })]));
+        assert_eq!(Day25.first_puzzle(&blueprint).unwrap(), "3");
 
-        tape.insert('B', Box::new(|tape: &mut Tape | -> char {
-            if tape.get() {
-                tape.set(true);
-                tape.move_right();
-            }
-            else {
-                tape.set(true);
-                tape.move_left();
-            }
-            'A'
-        }));
-        tape.select('A');
+        let unknown = BLUEPRINT.replace("Continue with state B.", "Continue with state C.");
+        assert_eq!(format!("{}", Blueprint::parse(&unknown).unwrap_err()), "parse error at 8:1: unknown state: C");
+        let incomplete = BLUEPRINT.replace("    - Move one slot to the left.\n    - Continue with state A.", "");
+        assert_eq!(format!("{}", Blueprint::parse(&incomplete).unwrap_err()), "parse error at 14:1: state B has incomplete rule for value 0");
+    }
+
+    #[test]
+    fn first_puzzle() {
+        let blueprint = Blueprint::parse(BLUEPRINT).unwrap();
+        let mut tape = Tape::from_blueprint(&blueprint);
+        assert!(tape.select('A'));
+        assert!(!tape.select('C'));
+        assert!(tape.select('A'));
 
         for _ in 0 .. 6 {
             println!("{}", tape);
-            tape.execute();
+            tape.execute().unwrap();
         }
         println!("{}", tape);
-        println!("checksum {}", tape.checksum());
+        assert_eq!(format!("{}", tape), " 1  1 [0] 1 ");
+        assert_eq!(tape.checksum(), 3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_multi_symbol() {
+        let blueprint = Blueprint::parse("Begin in state A.
+Perform a diagnostic checksum after 200 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 2.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 2:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 2.
+    - Move one slot to the right.
+    - Continue with state B.
+").unwrap();
+        let mut tape = Tape::from_blueprint(&blueprint);
+        tape.select(blueprint.start);
+        for _ in 0 .. 200 {
+            tape.execute().unwrap();
+        }
+        assert_eq!(tape.checksum(), 200);
+        assert_eq!(tape.get(), 0);
+
+        let mut tape = Tape::from_blueprint(&blueprint);
+        tape.select('B');
+        tape.set(1);
+        assert_eq!(format!("{}", tape.execute().unwrap_err()), "invalid state: state B has no rule for value 1");
+    }
+}
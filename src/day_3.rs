@@ -1,21 +1,56 @@
     use std::f64;
+    use error::Error;
+    use solution::Solution;
 
-    pub fn first_puzzle() -> String
-    {
-        format!("{}", steps(277678))
-    }
+    pub struct Day3;
 
-    pub fn second_puzzle() -> String
+    impl Solution for Day3
     {
-        let mut i = 1;
-        let mut g = grid(i);
-        while g <= 277678
+        type Input = i64;
+
+        fn day(&self) -> u32
         {
-            i+= 1;
-            g = grid(i)
+            3
+        }
+
+        fn name(&self) -> &'static str
+        {
+            "Spiral Memory"
+        }
+
+        fn input_path(&self) -> &'static str
+        {
+            "memory.txt"
+        }
+
+        fn parse(&self, input: &str) -> Result<i64, Error>
+        {
+            let token = input.trim();
+            match token.parse::<i64>()
+            {
+                Ok(square) if square >= 1 => Ok(square),
+                Ok(_) => Err(Error::at_token(1, input, token, format!("invalid square {}: squares are numbered from 1", token))),
+                Err(e) => Err(Error::at_token(1, input, token, format!("invalid square {}: {}", token, e))),
+            }
         }
 
-        format!("{}", g)
+        fn first_puzzle(&self, square: &i64) -> Result<String, Error>
+        {
+            Ok(format!("{}", steps(*square)))
+        }
+
+        fn second_puzzle(&self, square: &i64) -> Result<String, Error>
+        {
+            let mut i = 1;
+            let mut g = grid(i);
+            while g <= *square
+            {
+                i+= 1;
+                g = grid(i)
+            }
+
+            Ok(format!("{}", g))
+        }
     }
 
     fn x(n: i64) -> i64
@@ -82,9 +117,9 @@
     fn adjacent_coord(x: i64, y: i64) -> Vec<(i64, i64)>
     {
         let mut adj = Vec::new();
-        for dx in vec![-1,0, 1]
+        for dx in [-1,0, 1]
         {
-            for dy in vec![-1, 0, 1]
+            for dy in [-1, 0, 1]
             {
                 if dx != 0 || dy != 0
                 {
@@ -95,7 +130,7 @@
         adj
     }
 
-    fn adjacent_sum(x: i64, y: i64, grid: &Vec<i64>) -> i64
+    fn adjacent_sum(x: i64, y: i64, grid: &[i64]) -> i64
     {
         let mut sum = 0;
         for (a_x, a_y) in adjacent_coord(x, y)
@@ -133,6 +168,15 @@ mod tests
 {
     use super::*;
 
+    #[test]
+    fn test_parse()
+    {
+        assert_eq!(Day3.parse(" 23\n").unwrap(), 23);
+        assert_eq!(Day3.parse("0").unwrap_err().to_string(), "parse error at 1:1: invalid square 0: squares are numbered from 1");
+        assert_eq!(Day3.parse(" -5").unwrap_err().to_string(), "parse error at 1:2: invalid square -5: squares are numbered from 1");
+        assert!(Day3.parse("x").is_err());
+    }
+
     #[test]
     fn first_puzzle()
     {
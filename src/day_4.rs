@@ -1,29 +1,52 @@
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::fs::File;
 use std::collections::HashSet;
 use std::collections::HashMap;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let valid_count = count_valid_passphrases("passphrases.txt", has_repeated_words);
-    format!("{}",valid_count)
-}
+pub struct Day4;
 
-pub fn second_puzzle() -> String
+impl Solution for Day4
 {
-    let valid_count = count_valid_passphrases("passphrases.txt", has_anagrams);
-    format!("{}", valid_count)
+    type Input = Vec<String>;
+
+    fn day(&self) -> u32
+    {
+        4
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "High-Entropy Passphrases"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "passphrases.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<String>, Error>
+    {
+        Ok(input.lines().map(String::from).collect())
+    }
+
+    fn first_puzzle(&self, passphrases: &Vec<String>) -> Result<String, Error>
+    {
+        let valid_count = count_valid_passphrases(passphrases, has_repeated_words);
+        Ok(format!("{}",valid_count))
+    }
+
+    fn second_puzzle(&self, passphrases: &Vec<String>) -> Result<String, Error>
+    {
+        let valid_count = count_valid_passphrases(passphrases, has_anagrams);
+        Ok(format!("{}", valid_count))
+    }
 }
 
-pub fn count_valid_passphrases<F: Fn(&str) -> bool>(file: &str, invalid_phrase: F) -> usize
+pub fn count_valid_passphrases<F: Fn(&str) -> bool>(passphrases: &[String], invalid_phrase: F) -> usize
 {
-    let file = File::open(file).expect("Failed to open passphrases file");
-    let reader = BufReader::new(file);
-    reader.lines()
-          .filter_map(|res| res.ok())
-          .filter(|line| !invalid_phrase(&line))
-          .count()
+    passphrases.iter()
+               .filter(|line| !invalid_phrase(line))
+               .count()
 }
 
 fn has_repeated_words(phrase: &str) -> bool
@@ -84,18 +107,18 @@ mod tests
     #[test]
     fn first_puzzle()
     {
-        assert_eq!(false, has_repeated_words("aa bb cc dd ee"));
-        assert_eq!(true, has_repeated_words("aa bb cc dd aa"));
-        assert_eq!(false, has_repeated_words("aa bb cc dd aaa"));
+        assert!(!has_repeated_words("aa bb cc dd ee"));
+        assert!(has_repeated_words("aa bb cc dd aa"));
+        assert!(!has_repeated_words("aa bb cc dd aaa"));
     }
 
     #[test]
     fn second_puzzle()
     {
-        assert_eq!(false, has_anagrams("abcde fghij"));
-        assert_eq!(true, has_anagrams("abcde xyz ecdab"));
-        assert_eq!(false, has_anagrams("a ab abc abd abf abj"));
-        assert_eq!(false, has_anagrams("iiii oiii ooii oooi oooo"));
-        assert_eq!(true, has_anagrams("oiii ioii iioi iiio"));
+        assert!(!has_anagrams("abcde fghij"));
+        assert!(has_anagrams("abcde xyz ecdab"));
+        assert!(!has_anagrams("a ab abc abd abf abj"));
+        assert!(!has_anagrams("iiii oiii ooii oooi oooo"));
+        assert!(has_anagrams("oiii ioii iioi iiio"));
     }
 }
\ No newline at end of file
@@ -1,28 +1,57 @@
-use std::io::prelude::*; 
-use std::io::BufReader; 
-use std::fs::File; 
- 
-pub fn first_puzzle() -> String 
-{ 
-    let mut jumps = read_jumps("jumps.txt");
-    format!("{}", count_steps(&mut jumps, add_one)) 
-} 
- 
-pub fn second_puzzle() ->String 
-{ 
-    let mut jumps = read_jumps("jumps.txt");
-    format!("{}", count_steps(&mut jumps, add_or_sub_one)) 
-} 
+use error::Error;
+use solution::Solution;
+
+pub struct Day5;
 
-fn read_jumps(path: &str) -> Vec<i32>
+impl Solution for Day5
 {
+    type Input = Vec<i32>;
 
-    let file = File::open(path).expect("Failed to open jumps file"); 
-    let reader = BufReader::new(file); 
-    reader.lines() 
-          .filter_map(|res| res.ok()) 
-          .filter_map(|line| line.parse::<i32>().ok()) 
-          .collect::<Vec<i32>>()
+    fn day(&self) -> u32
+    {
+        5
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "A Maze of Twisty Trampolines, All Alike"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "jumps.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<i32>, Error>
+    {
+        read_jumps(input)
+    }
+
+    fn first_puzzle(&self, jumps: &Vec<i32>) -> Result<String, Error>
+    {
+        let mut jumps = jumps.clone();
+        Ok(format!("{}", count_steps(&mut jumps, add_one)))
+    }
+
+    fn second_puzzle(&self, jumps: &Vec<i32>) -> Result<String, Error>
+    {
+        let mut jumps = jumps.clone();
+        Ok(format!("{}", count_steps(&mut jumps, add_or_sub_one)))
+    }
+}
+
+fn read_jumps(input: &str) -> Result<Vec<i32>, Error>
+{
+    input.lines()
+         .enumerate()
+         .filter(|&(_, line)| !line.trim().is_empty())
+         .map(|(idx, line)| 
+         {
+             let token = line.trim();
+             token.parse::<i32>()
+                  .map_err(|e| Error::at_token(idx + 1, line, token, format!("invalid jump {}: {}", token, e)))
+         })
+         .collect()
 }
  
 fn count_steps<F: Fn(&mut i32)>(jumps: &mut [i32], adjust_offset: F) -> u32 
@@ -1,18 +1,53 @@
 use std::collections::HashMap;
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let (all_cycles, _) = cycles_count(BANKS);
-    format!("{}", all_cycles)
-}
+pub struct Day6;
 
-pub fn second_puzzle() -> String
+impl Solution for Day6
 {
-    let (_, interval_cycles) = cycles_count(BANKS);
-    format!("{}", interval_cycles)
-}
+    type Input = Vec<u32>;
+
+    fn day(&self) -> u32
+    {
+        6
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Memory Reallocation"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "banks.txt"
+    }
 
-static BANKS: &[u32] = &[14, 0, 15, 12, 11, 11, 3, 5, 1, 6, 8, 4, 9, 1, 8, 4];
+    fn parse(&self, input: &str) -> Result<Vec<u32>, Error>
+    {
+        let banks = input.split_whitespace()
+                         .map(|bank| bank.parse::<u32>()
+                                         .map_err(|e| Error::at_token(1, input, bank, format!("invalid bank {}: {}", bank, e))))
+                         .collect::<Result<Vec<u32>, Error>>()?;
+        if banks.is_empty()
+        {
+            return Err(Error::parse(1, 1, "missing memory banks"));
+        }
+        Ok(banks)
+    }
+
+    fn first_puzzle(&self, banks: &Vec<u32>) -> Result<String, Error>
+    {
+        let (all_cycles, _) = cycles_count(banks);
+        Ok(format!("{}", all_cycles))
+    }
+
+    fn second_puzzle(&self, banks: &Vec<u32>) -> Result<String, Error>
+    {
+        let (_, interval_cycles) = cycles_count(banks);
+        Ok(format!("{}", interval_cycles))
+    }
+}
 
 fn cycles_count(banks: &[u32]) -> (u32, u32)
 {
@@ -49,7 +84,7 @@ fn cycles_count(banks: &[u32]) -> (u32, u32)
 
 fn max_el_idx(elems: &[u32]) -> usize
 {
-    let max_idx = (0 .. elems.len()).fold(0, |idx_max, idx| 
+    (0 .. elems.len()).fold(0, |idx_max, idx| 
         {
             if elems[idx] > elems[idx_max] 
             {
@@ -58,9 +93,7 @@ fn max_el_idx(elems: &[u32]) -> usize
             else {
                 idx_max
             }
-        });
-
-    max_idx
+        })
 }
 
 #[cfg(test)] 
@@ -73,6 +106,6 @@ mod tests
     { 
         assert_eq!(1, max_el_idx(&[0, 3, 0, 1, 3]));
         assert_eq!(3, max_el_idx(&[4, 3, 6, 8, 0]));
-        assert_eq!((5, 4), cycles_count(&mut [0, 2, 7, 0]))
+        assert_eq!((5, 4), cycles_count(&[0, 2, 7, 0]))
     } 
 }
\ No newline at end of file
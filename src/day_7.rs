@@ -1,40 +1,58 @@
 use std::collections::HashSet;
 use std::collections::HashMap;
-use std::io::prelude::*; 
-use std::io::BufReader; 
-use std::fs::File; 
+use error::Error;
+use solution::Solution;
 
+pub struct Day7;
 
-pub fn first_puzzle() -> String
+impl Solution for Day7
 {
-    let root = read_tree("tower.txt").unwrap();
-    format!("{}", root.name)
-}
+    type Input = Node;
 
-pub fn second_puzzle() -> String
-{
-    let root = read_tree("tower.txt").unwrap();
-    format!("{}", root.balance())
+    fn day(&self) -> u32
+    {
+        7
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Recursive Circus"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "tower.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Node, Error>
+    {
+        read_tree(input)
+    }
+
+    fn first_puzzle(&self, root: &Node) -> Result<String, Error>
+    {
+        Ok(root.name.clone())
+    }
+
+    fn second_puzzle(&self, root: &Node) -> Result<String, Error>
+    {
+        Ok(format!("{}", root.balance()?))
+    }
 }
 
 
-fn read_tree(path: &str) -> Result<Node, &'static str>
+fn read_tree(input: &str) -> Result<Node, Error>
 {
     let mut parser = TreeParser::new();
-    let file = File::open(path).expect("Failed to open tower file"); 
-    let reader = BufReader::new(file); 
-    for line in reader.lines().filter_map(|res| res.ok())
+    for (idx, line) in input.lines().enumerate().filter(|&(_, line)| !line.trim().is_empty())
     {
-        if let Err(msg) = parser.parse(&line)
-        {
-            println!("paring error: {}", msg);
-        }
+        parser.parse(line).map_err(|e| e.on_line(idx + 1))?;
     }
 
     parser.build_tree()
 }
 
-struct Node
+pub struct Node
 {
     name: String,
     weight: u32,
@@ -47,7 +65,7 @@ impl Node
     {
         Node{
             name: String::from(name),
-            weight: weight,
+            weight,
             children: Vec::new()
         }
     }
@@ -78,37 +96,36 @@ impl Node
         let different_weights = self.children.iter()
                                              .map(|child| child.branch_weight())
                                              .collect::<HashSet<_>>();
-        different_weights.len() == 1 || different_weights.len() == 0
+        different_weights.len() <= 1
     }
 
     fn find_unbalanced(self: &Self) -> &Node
     {
-        for child in self.children.iter().filter(|ch| !ch.is_balanced())
+        match self.children.iter().find(|ch| !ch.is_balanced())
         {
-            return child.find_unbalanced();
+            Some(child) => child.find_unbalanced(),
+            None => self
         }
-
-        self
     }
 
-    fn balance(self: &Self) -> i32
+    fn balance(self: &Self) -> Result<i32, Error>
     {
         let node = self.find_unbalanced();
-        let balanced_subbranch_weight = node.balanced_subbranch_weight();
+        let balanced_subbranch_weight = node.balanced_subbranch_weight()?;
         for child in node.children.iter()
         {
             let subbranch_weight = child.branch_weight();
             if subbranch_weight != balanced_subbranch_weight
             {
                 let diff = balanced_subbranch_weight as i32 - subbranch_weight as i32;
-                return child.weight as i32 + diff 
+                return Ok(child.weight as i32 + diff)
             }            
         }
 
-        0
+        Err(Error::no_solution("tower is already balanced"))
     }
 
-    fn balanced_subbranch_weight(self: &Self) -> u32
+    fn balanced_subbranch_weight(self: &Self) -> Result<u32, Error>
     {
         let mut weight_count = HashMap::new();
         for weight in self.children.iter().map(|ch| ch.branch_weight())
@@ -117,23 +134,22 @@ impl Node
             *count += 1;
         }
 
-        match weight_count.len()
+        let mut weights = weight_count.iter();
+        match (weights.next(), weights.next(), weights.next())
         {
-            0 => 0,
-            1 => *weight_count.keys().nth(0).unwrap(),
-            2 => 
+            (None, _, _) => Ok(0),
+            (Some((&weight, _)), None, _) => Ok(weight),
+            (Some((&first_weight, &first_count)), Some((&second_weight, &second_count)), None) =>
             {
-                let (&first_weight, &first_count) = weight_count.iter().nth(0).unwrap();
-                let (&second_weight, &second_count) = weight_count.iter().nth(1).unwrap();
                 match (first_count, second_count)
                 {
-                    (1, 1) => panic!("Ambigious which weight should be choosed (should be handled)"),
-                    (1, _) => second_weight,
-                    (_, 1) => first_weight,
-                    (_, _) => panic!("Assumed single node with wrong weight")
+                    (1, 1) => Err(Error::no_solution("ambigious which weight should be choosed")),
+                    (1, _) => Ok(second_weight),
+                    (_, 1) => Ok(first_weight),
+                    (_, _) => Err(Error::no_solution("assumed single node with wrong weight"))
                 }
             }
-            _ => panic!("Assumed single node with wrong weight")
+            _ => Err(Error::no_solution("assumed single node with wrong weight"))
         }
     }
 }
@@ -155,27 +171,30 @@ impl TreeParser
         }
     }
 
-    pub fn build_tree(self: Self ) -> Result<Node, &'static str>
+    pub fn build_tree(self: Self ) -> Result<Node, Error>
     {
         let mut root = self.find_root()?;
-        self.add_children(&mut root);
+        self.add_children(&mut root)?;
 
         Ok(root)
     }
 
-    fn add_children(self: &Self, node: &mut Node)
+    fn add_children(self: &Self, node: &mut Node) -> Result<(), Error>
     {
         if let Some(children) = self.edges.get(&node.name) {
             for name in children.iter()
             {
-                let mut child = Node::new(name, *self.nodes.get(name).unwrap());
-                self.add_children(&mut child);
+                let weight = self.nodes.get(name)
+                                       .ok_or_else(|| Error::invalid_state(format!("unknown program: {}", name)))?;
+                let mut child = Node::new(name, *weight);
+                self.add_children(&mut child)?;
                 node.add_child(child);
             }
         }
+        Ok(())
     }
 
-    fn find_root(self: &Self) -> Result<Node, &'static str>
+    fn find_root(self: &Self) -> Result<Node, Error>
     {
         let parents = self.edges.keys().collect::<HashSet<_>>();
         let children = self.edges.values()
@@ -183,23 +202,24 @@ impl TreeParser
                                  .collect::<HashSet<_>>();
 
         let roots = parents.difference(&children).cloned().collect::<Vec<_>>();
-        if roots.len() == 0
+        if roots.is_empty()
         {
-            Err("No root found.")
+            Err(Error::no_solution("no root found"))
         }
         else if roots.len() > 1
         {
-            Err("More than one root found.")
+            Err(Error::no_solution("more than one root found"))
         }
         else
         {
-            let name = roots.get(0).unwrap();
-            let weight = self.nodes.get(*name).unwrap();
+            let name = roots[0];
+            let weight = self.nodes.get(name)
+                                   .ok_or_else(|| Error::invalid_state(format!("unknown program: {}", name)))?;
             Ok(Node::new(name, *weight))
         }
     }
 
-    pub fn parse(self: &mut Self, line: &str) -> Result<(), &str>
+    pub fn parse(self: &mut Self, line: &str) -> Result<(), Error>
     {
         let mut name = String::new();
         let mut weight = 0;
@@ -219,18 +239,18 @@ impl TreeParser
                     }
                     else
                     {
-                        return Err("Failed to read height")
+                        return Err(Error::at_token(1, line, token, format!("failed to read weight: {}", token)))
                     }
                 },
                 2 if token == "->" => {},
                 2 if token != "->" => 
                 { 
-                    return Err("Third token is not equal to '->'")
+                    return Err(Error::at_token(1, line, token, "third token is not equal to '->'"))
                 },
                 _ => 
                 { 
-                   let child_name = String::from(token.trim_right_matches(','));
-                   let entry = self.edges.entry(name.clone()).or_insert(Vec::new());
+                   let child_name = String::from(token.trim_end_matches(','));
+                   let entry = self.edges.entry(name.clone()).or_default();
                    entry.push(child_name.clone());
                 }
             }
@@ -274,7 +294,7 @@ cntj (57)";
        assert_eq!(41, root.weight);
        assert_eq!(3, root.children.len());
        assert_eq!(778, root.branch_weight());
-       assert_eq!(false, root.is_balanced());
+       assert!(!root.is_balanced());
 
     }
 
@@ -293,9 +313,9 @@ cntj (57)";
        assert_eq!(41, unbalanced.weight);
        assert_eq!(3, unbalanced.children.len());
        assert_eq!(778, unbalanced.branch_weight());
-       assert_eq!(false, unbalanced.is_balanced());
-       assert_eq!(unbalanced.balanced_subbranch_weight(), 243);
-       assert_eq!(root.balance(), 60);
+       assert!(!unbalanced.is_balanced());
+       assert_eq!(unbalanced.balanced_subbranch_weight().unwrap(), 243);
+       assert_eq!(root.balance().unwrap(), 60);
     }
 }
 
@@ -0,0 +1,325 @@
+use std::fmt;
+use std::num::IntErrorKind;
+use error::{self, Error};
+
+/// Position of a node in the source, `start` and `end` are 1-based columns, `end` excluded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Span
+{
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Spanned<T>
+{
+    pub node: T,
+    pub span: Span,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action
+{
+    Inc,
+    Dec,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Comparison
+{
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    Eq,
+    Ne,
+}
+
+/// Range of the register values and literals, `I32` as in the puzzle.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Width
+{
+    #[default]
+    I32,
+    I64,
+    I128,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Condition
+{
+    pub register: Spanned<String>,
+    pub comparison: Spanned<Comparison>,
+    pub value: Spanned<i128>,
+}
+
+/// `target action amount if condition`, such as `b inc 5 if a > 1`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Instruction
+{
+    pub target: Spanned<String>,
+    pub action: Spanned<Action>,
+    pub amount: Spanned<i128>,
+    pub condition: Condition,
+    pub span: Span,
+}
+
+impl Span
+{
+    fn of(line_no: usize, line: &str, token: &str) -> Span
+    {
+        let start = error::column(line, token);
+        Span { line: line_no, start, end: start + token.len() }
+    }
+}
+
+impl Action
+{
+    pub fn parse(token: &str) -> Option<Action>
+    {
+        match token
+        {
+            "inc" => Some(Action::Inc),
+            "dec" => Some(Action::Dec),
+            _ => None,
+        }
+    }
+}
+
+impl Comparison
+{
+    pub fn parse(token: &str) -> Option<Comparison>
+    {
+        match token
+        {
+            ">" => Some(Comparison::Gt),
+            "<" => Some(Comparison::Lt),
+            ">=" => Some(Comparison::Ge),
+            "<=" => Some(Comparison::Le),
+            "==" => Some(Comparison::Eq),
+            "!=" => Some(Comparison::Ne),
+            _ => None,
+        }
+    }
+
+    pub fn holds(self: &Self, lhs: i128, rhs: i128) -> bool
+    {
+        match *self
+        {
+            Comparison::Gt => lhs > rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Ge => lhs >= rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+        }
+    }
+}
+
+impl Width
+{
+    pub fn parse(name: &str) -> Option<Width>
+    {
+        match name
+        {
+            "i32" => Some(Width::I32),
+            "i64" => Some(Width::I64),
+            "i128" => Some(Width::I128),
+            _ => None,
+        }
+    }
+
+    pub fn contains(self: &Self, value: i128) -> bool
+    {
+        match *self
+        {
+            Width::I32 => i32::MIN as i128 <= value && value <= i32::MAX as i128,
+            Width::I64 => i64::MIN as i128 <= value && value <= i64::MAX as i128,
+            Width::I128 => true,
+        }
+    }
+}
+
+fn spanned<T>(line_no: usize, line: &str, token: &str, node: T) -> Spanned<T>
+{
+    Spanned { node, span: Span::of(line_no, line, token) }
+}
+
+fn register(line_no: usize, line: &str, token: &str) -> Result<Spanned<String>, Error>
+{
+    if token.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        Ok(spanned(line_no, line, token, String::from(token)))
+    }
+    else
+    {
+        Err(Error::at_token(line_no, line, token, format!("invalid register name: {}", token)))
+    }
+}
+
+fn literal(line_no: usize, line: &str, token: &str, width: Width) -> Result<Spanned<i128>, Error>
+{
+    match token.parse::<i128>()
+    {
+        Ok(value) if width.contains(value) => Ok(spanned(line_no, line, token, value)),
+        Ok(_) => Err(Error::at_token(line_no, line, token, format!("integer out of range for {}: {}", width, token))),
+        Err(ref error) if *error.kind() == IntErrorKind::PosOverflow || *error.kind() == IntErrorKind::NegOverflow =>
+        {
+            Err(Error::at_token(line_no, line, token, format!("integer out of range for {}: {}", Width::I128, token)))
+        },
+        Err(_) => Err(Error::at_token(line_no, line, token, format!("invalid integer: {}", token))),
+    }
+}
+
+impl Instruction
+{
+    /// Parses a single instruction with `i32` literals, errors and spans are on line 1.
+    pub fn parse(line: &str) -> Result<Instruction, Error>
+    {
+        Instruction::parse_line(1, line, Width::I32)
+    }
+
+    /// Errors on literals outside `width`.
+    pub fn parse_line(line_no: usize, line: &str, width: Width) -> Result<Instruction, Error>
+    {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if tokens.len() != 7
+        {
+            let column = tokens.get(7).map_or(line.len() + 1, |token| error::column(line, token));
+            return Err(Error::parse(line_no, column, format!("expected 7 tokens, found {}", tokens.len())));
+        }
+        if tokens[3] != "if"
+        {
+            return Err(Error::at_token(line_no, line, tokens[3], format!("expected if, found {}", tokens[3])));
+        }
+
+        let action = Action::parse(tokens[1])
+            .ok_or_else(|| Error::at_token(line_no, line, tokens[1], format!("expected inc or dec, found {}", tokens[1])))?;
+        let comparison = Comparison::parse(tokens[5])
+            .ok_or_else(|| Error::at_token(line_no, line, tokens[5], format!("expected one of > < >= <= == !=, found {}", tokens[5])))?;
+        let first = error::column(line, tokens[0]);
+        let last = tokens[6];
+        Ok(Instruction {
+            target: register(line_no, line, tokens[0])?,
+            action: spanned(line_no, line, tokens[1], action),
+            amount: literal(line_no, line, tokens[2], width)?,
+            condition: Condition {
+                register: register(line_no, line, tokens[4])?,
+                comparison: spanned(line_no, line, tokens[5], comparison),
+                value: literal(line_no, line, last, width)?,
+            },
+            span: Span { line: line_no, start: first, end: error::column(line, last) + last.len() },
+        })
+    }
+}
+
+/// Parses every non-empty line of `input` with `i32` literals, spans refer to its lines.
+pub fn parse_program(input: &str) -> Result<Vec<Instruction>, Error>
+{
+    parse_program_as(input, Width::I32)
+}
+
+/// `parse_program` with literals in `width`.
+pub fn parse_program_as(input: &str, width: Width) -> Result<Vec<Instruction>, Error>
+{
+    input.lines()
+         .enumerate()
+         .filter(|&(_, line)| !line.trim().is_empty())
+         .map(|(idx, line)| Instruction::parse_line(idx + 1, line, width))
+         .collect()
+}
+
+/// One instruction per line in the canonical `b inc 5 if a > 1` layout.
+pub fn print(program: &[Instruction]) -> String
+{
+    program.iter().map(|instruction| format!("{}\n", instruction)).collect()
+}
+
+impl fmt::Display for Action
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{}", if *self == Action::Inc { "inc" } else { "dec" })
+    }
+}
+
+impl fmt::Display for Comparison
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let op = match *self
+        {
+            Comparison::Gt => ">",
+            Comparison::Lt => "<",
+            Comparison::Ge => ">=",
+            Comparison::Le => "<=",
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl fmt::Display for Width
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let name = match *self
+        {
+            Width::I32 => "i32",
+            Width::I64 => "i64",
+            Width::I128 => "i128",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Condition
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{} {} {}", self.register.node, self.comparison.node, self.value.node)
+    }
+}
+
+impl fmt::Display for Instruction
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{} {} {} if {}", self.target.node, self.action.node, self.amount.node, self.condition)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_parse()
+    {
+        let input = "b inc 5 if a > 1\na inc 1 if b < 5\n\n  c dec -10 if a >= 1\nc inc -20 if c == 10\n";
+        let program = parse_program(input).unwrap();
+        assert_eq!(program[2].span, Span { line: 4, start: 3, end: 22 });
+        assert_eq!(program[2].amount, Spanned { node: -10, span: Span { line: 4, start: 9, end: 12 } });
+        assert_eq!(program[3].condition.comparison.node, Comparison::Eq);
+        assert_eq!(print(&program), "b inc 5 if a > 1\na inc 1 if b < 5\nc dec -10 if a >= 1\nc inc -20 if c == 10\n");
+        assert_eq!(parse_program(&print(&program)).unwrap()[1], program[1]);
+
+        assert_eq!(parse_program("a inc 1 if b < 5\nb inc 1 if a => 1").unwrap_err().to_string(),
+                   "parse error at 2:14: expected one of > < >= <= == !=, found =>");
+        assert_eq!(Instruction::parse("a add 1 if b < 5").unwrap_err().to_string(),
+                   "parse error at 1:3: expected inc or dec, found add");
+        assert_eq!(Instruction::parse("a inc x if b < 5").unwrap_err().to_string(),
+                   "parse error at 1:7: invalid integer: x");
+        assert_eq!(Instruction::parse("a inc 1 if b < 5 c").unwrap_err().to_string(),
+                   "parse error at 1:18: expected 7 tokens, found 8");
+
+        assert_eq!(parse_program("a inc 1 if b < 5\nb dec 3000000000 if a > 0").unwrap_err().to_string(),
+                   "parse error at 2:7: integer out of range for i32: 3000000000");
+        assert_eq!(parse_program_as("b dec 3000000000 if a > 0", Width::I64).unwrap()[0].amount.node, 3000000000);
+        assert_eq!(Instruction::parse_line(1, "a inc 1 if b < -170141183460469231731687303715884105729", Width::I128).unwrap_err().to_string(),
+                   "parse error at 1:16: integer out of range for i128: -170141183460469231731687303715884105729");
+    }
+}
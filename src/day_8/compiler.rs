@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use day_18::{self, Assembler, Dialect, Operand, Register};
+use error::Error;
+use super::{Action, Comparison, Instruction, Registers, Spanned, Width};
+
+/// Day 18 program computing the same registers as a day 8 program.
+pub struct Compiled
+{
+    pub instructions: Vec<day_18::Instruction>,
+    /// Register allocated to every day 8 register, from `a` in order of first use.
+    pub registers: BTreeMap<String, Register>,
+    /// Register the guards compute in, following the allocated ones.
+    pub scratch: Register,
+}
+
+/// Where a guard jumps to, the `add` it protects or past it.
+#[derive(Clone, Copy)]
+enum Target
+{
+    Apply,
+    Skip,
+}
+
+/// Guard instruction, its jumps resolved once the guard is complete.
+enum Step
+{
+    Plain(day_18::Instruction),
+    Branch(Operand, Target),
+}
+
+/// Jumps to `target` when `reg >= bound`, falling through otherwise, `None` stands for a bound above every `i128`.
+/// `reg - bound` is only computed when both have the same sign, so nothing overflows.
+fn at_least(steps: &mut Vec<Step>, scratch: Register, reg: Register, bound: Option<i128>, target: Target)
+{
+    let bound = match bound
+    {
+        Some(bound) if bound <= i64::MIN as i128 =>
+        {
+            steps.push(Step::Branch(Operand::Const(1), target));
+            return;
+        },
+        Some(bound) if bound <= i64::MAX as i128 => bound as i64,
+        _ => return,
+    };
+    if bound > 0
+    {
+        steps.push(Step::Plain(day_18::Instruction::Jgz(Operand::Reg(reg), Operand::Const(2))));
+        steps.push(Step::Plain(day_18::Instruction::Jgz(Operand::Const(1), Operand::Const(6))));
+    }
+    else if bound < 0
+    {
+        steps.push(Step::Branch(Operand::Reg(reg), target));
+    }
+    steps.push(Step::Plain(day_18::Instruction::Set(scratch, Operand::Reg(reg))));
+    if bound != 0
+    {
+        steps.push(Step::Plain(day_18::Instruction::Add(scratch, Operand::Const(-bound))));
+    }
+    // `scratch >= 0` as `scratch > 0`, then `scratch + 1 > 0`.
+    steps.push(Step::Branch(Operand::Reg(scratch), target));
+    steps.push(Step::Plain(day_18::Instruction::Add(scratch, Operand::Const(1))));
+    steps.push(Step::Branch(Operand::Reg(scratch), target));
+}
+
+/// `value`, computed from `literal`, as a day 18 constant, erroring at `literal` when it does not fit or could not be computed.
+fn constant(value: Option<i128>, literal: &Spanned<i128>) -> Result<i64, Error>
+{
+    value.and_then(|value| i64::try_from(value).ok())
+        .ok_or_else(|| Error::parse(literal.span.line, literal.span.start, format!("{} is out of range for day 18 registers", literal.node)))
+}
+
+/// Compiles `program` to the Duet dialect, every guard jumping over the `add` it protects when it fails.
+/// Guards never fault, whatever their literal, but amounts have to fit an `i64`.
+pub fn compile(program: &[Instruction]) -> Result<Compiled, Error>
+{
+    let mut registers = BTreeMap::new();
+    for instruction in program
+    {
+        for name in &[&instruction.condition.register.node, &instruction.target.node]
+        {
+            if !registers.contains_key(name.as_str())
+            {
+                let next = registers.len();
+                registers.insert(name.to_string(), next);
+            }
+        }
+    }
+    let scratch = registers.len();
+
+    let mut instructions = Vec::new();
+    for instruction in program
+    {
+        let reg = registers[&instruction.condition.register.node];
+        let value = Some(instruction.condition.value.node);
+        // `None` for an `i128::MAX` literal, which no register exceeds.
+        let above = instruction.condition.value.node.checked_add(1);
+        let mut steps = Vec::new();
+        let otherwise = match instruction.condition.comparison.node
+        {
+            Comparison::Ge =>
+            {
+                at_least(&mut steps, scratch, reg, value, Target::Apply);
+                Target::Skip
+            },
+            Comparison::Gt =>
+            {
+                at_least(&mut steps, scratch, reg, above, Target::Apply);
+                Target::Skip
+            },
+            Comparison::Le =>
+            {
+                at_least(&mut steps, scratch, reg, above, Target::Skip);
+                Target::Apply
+            },
+            Comparison::Lt =>
+            {
+                at_least(&mut steps, scratch, reg, value, Target::Skip);
+                Target::Apply
+            },
+            Comparison::Eq =>
+            {
+                at_least(&mut steps, scratch, reg, above, Target::Skip);
+                at_least(&mut steps, scratch, reg, value, Target::Apply);
+                Target::Skip
+            },
+            Comparison::Ne =>
+            {
+                at_least(&mut steps, scratch, reg, above, Target::Apply);
+                at_least(&mut steps, scratch, reg, value, Target::Skip);
+                Target::Apply
+            },
+        };
+        if let Target::Skip = otherwise
+        {
+            steps.push(Step::Branch(Operand::Const(1), Target::Skip));
+        }
+
+        let apply = steps.len() as i64;
+        for (index, step) in steps.into_iter().enumerate()
+        {
+            instructions.push(match step
+            {
+                Step::Plain(instruction) => instruction,
+                Step::Branch(condition, Target::Apply) => day_18::Instruction::Jgz(condition, Operand::Const(apply - index as i64)),
+                Step::Branch(condition, Target::Skip) => day_18::Instruction::Jgz(condition, Operand::Const(apply + 1 - index as i64)),
+            });
+        }
+
+        let amount = if instruction.action.node == Action::Inc { Some(instruction.amount.node) } else { instruction.amount.node.checked_neg() };
+        let amount = constant(amount, &instruction.amount)?;
+        instructions.push(day_18::Instruction::Add(registers[&instruction.target.node], Operand::Const(amount)));
+    }
+
+    Ok(Compiled { instructions, registers, scratch })
+}
+
+impl Compiled
+{
+    /// Day 8 registers of width `i64` held by `registers`, every allocated register counting as used.
+    pub fn read(self: &Self, registers: &day_18::Registers) -> Registers
+    {
+        let mut regs = Registers::with_width(Width::I64);
+        for (name, &reg) in &self.registers
+        {
+            *regs.get(name) = registers[reg] as i128;
+        }
+        regs
+    }
+
+    /// Runs the program on an `Assembler` starting with every register at 0.
+    pub fn run(self: &Self) -> Result<Registers, Error>
+    {
+        let mut assembler = Assembler::with_initial(day_18::Registers::new(), Dialect::Duet, &self.instructions)?;
+        assembler.run(u64::MAX)?;
+        Ok(self.read(&assembler.registers))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use super::super::{parse_program, parse_program_as, run};
+
+    #[test]
+    fn test_compile()
+    {
+        let program = parse_program("b inc 5 if a > 1\na inc 1 if b < 5\nc dec -10 if a >= 1\nc inc -20 if c == 10").unwrap();
+        let compiled = compile(&program).unwrap();
+        assert_eq!(compiled.registers.iter().map(|(name, &reg)| (name.as_str(), reg)).collect::<Vec<_>>(), vec![("a", 0), ("b", 1), ("c", 2)]);
+        let listing = compiled.instructions.iter().map(|instruction| format!("{}\n", instruction)).collect::<String>();
+        assert!(listing.starts_with("jgz a 2\njgz 1 6\nset d a\nadd d -2\njgz d 4\nadd d 1\njgz d 2\njgz 1 2\nadd b 5\n"));
+        assert!(listing.ends_with("jgz c 2\njgz 1 6\nset d c\nadd d -11\njgz d 12\nadd d 1\njgz d 10\n\
+                                   jgz c 2\njgz 1 6\nset d c\nadd d -10\njgz d 4\nadd d 1\njgz d 2\njgz 1 2\nadd c -20\n"));
+
+        let programs = [
+            "count inc 7 if x == 0\ncount dec 3 if count != 7\nx dec 2 if count <= 4\ny inc 9 if x < -2\ny inc 1 if x >= -2",
+            "abc inc -4 if q != 0\nq dec 1 if abc > -5\nabc inc 100 if q == -1\nzz dec -6 if abc < 96\nzz inc 2 if abc >= 96\nq inc 1 if zz > 1",
+        ];
+        for text in &programs
+        {
+            let program = parse_program(text).unwrap();
+            let compiled = compile(&program).unwrap();
+            let mut expected = Registers::new();
+            run(&program, &mut expected).unwrap();
+            let actual = compiled.run().unwrap();
+            for name in compiled.registers.keys()
+            {
+                assert_eq!((name, actual.value(name)), (name, expected.value(name)));
+            }
+            assert_eq!(actual.max(), expected.max());
+        }
+
+        let program = parse_program_as("b dec 5 if c == 0\na inc 1 if b > 9223372036854775806", Width::I64).unwrap();
+        let actual = compile(&program).unwrap().run().unwrap();
+        assert_eq!((actual.value("a"), actual.value("b")), (0, -5));
+        let program = parse_program_as("a inc 1 if b > 9223372036854775807\nc inc 1 if b < 9223372036854775808", Width::I128).unwrap();
+        let actual = compile(&program).unwrap().run().unwrap();
+        assert_eq!((actual.value("a"), actual.value("c")), (0, 1));
+        let program = parse_program_as("a dec -9223372036854775808 if b == 0", Width::I128).unwrap();
+        assert_eq!(compile(&program).err().unwrap().to_string(), "parse error at 1:7: -9223372036854775808 is out of range for day 18 registers");
+        let program = parse_program_as("a inc 9223372036854775807 if b > -1\na inc 1 if b == 0", Width::I64).unwrap();
+        assert_eq!(compile(&program).unwrap().run().err().unwrap().to_string(), "fault: ic 18: arithmetic overflow: add b 1");
+        assert_eq!(run(&program, &mut Registers::with_width(Width::I64)).unwrap_err().to_string(),
+                   "invalid state: line 2: i64 overflow: a inc 1 if b == 0");
+    }
+
+    #[test]
+    fn test_compile_extremes()
+    {
+        let values = [i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX - 1, i64::MAX];
+        for &x in &values
+        {
+            for &value in &values
+            {
+                for comparison in &[">", "<", ">=", "<=", "==", "!="]
+                {
+                    let text = format!("x inc {} if z == 0\ny inc 1 if x {} {}", x, comparison, value);
+                    let program = parse_program_as(&text, Width::I64).unwrap();
+                    let mut expected = Registers::with_width(Width::I64);
+                    run(&program, &mut expected).unwrap();
+                    let actual = compile(&program).unwrap().run().unwrap();
+                    assert_eq!((&text, actual.value("y")), (&text, expected.value("y")));
+                }
+            }
+            for &value in &[i128::MIN, i128::MIN + 1, i128::MAX - 1, i128::MAX]
+            {
+                for comparison in &[">", "<", ">=", "<=", "==", "!="]
+                {
+                    let text = format!("x inc {} if z == 0\ny inc 1 if x {} {}", x, comparison, value);
+                    let program = parse_program_as(&text, Width::I128).unwrap();
+                    let mut expected = Registers::with_width(Width::I128);
+                    run(&program, &mut expected).unwrap();
+                    let actual = compile(&program).unwrap().run().unwrap();
+                    assert_eq!((&text, actual.value("y")), (&text, expected.value("y")));
+                }
+            }
+        }
+
+        let program = parse_program_as("a dec -170141183460469231731687303715884105728 if b == 0", Width::I128).unwrap();
+        assert_eq!(compile(&program).err().unwrap().to_string(),
+                   "parse error at 1:7: -170141183460469231731687303715884105728 is out of range for day 18 registers");
+    }
+}
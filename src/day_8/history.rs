@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use error::Error;
+use super::{Instruction, Registers};
+
+/// Value of a register and the index of the instruction it was first reached at.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Extreme
+{
+    pub value: i128,
+    pub index: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RegisterHistory
+{
+    /// `(instruction index, value)` from the first instruction using the register, then after every change.
+    pub timeline: Vec<(usize, i128)>,
+    pub min: Extreme,
+    pub max: Extreme,
+}
+
+/// What happened to every register while running a program, and how often each condition held.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct History
+{
+    pub registers: BTreeMap<String, RegisterHistory>,
+    pub taken: Vec<u64>,
+}
+
+impl RegisterHistory
+{
+    fn new(index: usize, value: i128) -> RegisterHistory
+    {
+        let extreme = Extreme { value, index };
+        RegisterHistory { timeline: vec![(index, value)], min: extreme, max: extreme }
+    }
+
+    fn update(self: &mut Self, index: usize, value: i128)
+    {
+        if self.timeline.last().map(|&(_, last)| last) == Some(value)
+        {
+            return;
+        }
+        self.timeline.push((index, value));
+        if value < self.min.value
+        {
+            self.min = Extreme { value, index };
+        }
+        if value > self.max.value
+        {
+            self.max = Extreme { value, index };
+        }
+    }
+
+    pub fn last(self: &Self) -> i128
+    {
+        self.timeline.last().map_or(0, |&(_, value)| value)
+    }
+}
+
+impl History
+{
+    pub fn new() -> History
+    {
+        History::default()
+    }
+
+    /// Runs `program` once over `regs`, recording its history up to the first overflow.
+    pub fn run(program: &[Instruction], regs: &mut Registers) -> Result<History, Error>
+    {
+        let mut history = History::new();
+        for (index, instruction) in program.iter().enumerate()
+        {
+            history.execute(index, instruction, regs)?;
+        }
+        Ok(history)
+    }
+
+    /// Executes `instruction`, the one at `index` in its program, and records what it changed.
+    pub fn execute(self: &mut Self, index: usize, instruction: &Instruction, regs: &mut Registers) -> Result<bool, Error>
+    {
+        if self.taken.len() <= index
+        {
+            self.taken.resize(index + 1, 0);
+        }
+
+        let taken = instruction.execute(regs)?;
+        if taken
+        {
+            self.taken[index] += 1;
+        }
+        for name in &[&instruction.condition.register.node, &instruction.target.node]
+        {
+            let value = regs.value(name);
+            if taken || name.as_str() == instruction.condition.register.node
+            {
+                self.registers.entry(name.to_string())
+                    .and_modify(|register| register.update(index, value))
+                    .or_insert_with(|| RegisterHistory::new(index, value));
+            }
+        }
+        Ok(taken)
+    }
+
+    /// Register reaching the largest value, the first one by name on ties.
+    pub fn peak(self: &Self) -> Option<(&str, Extreme)>
+    {
+        self.registers.iter()
+            .fold(None, |best: Option<(&str, Extreme)>, (name, register)| match best
+            {
+                Some((_, max)) if max.value >= register.max.value => best,
+                _ => Some((name.as_str(), register.max)),
+            })
+    }
+
+    /// `register,min,min_at,max,max_at,last,changes`, one row per register by name.
+    pub fn write_registers_csv<W: Write>(self: &Self, mut output: W) -> io::Result<()>
+    {
+        writeln!(output, "register,min,min_at,max,max_at,last,changes")?;
+        for (name, register) in &self.registers
+        {
+            writeln!(output, "{},{},{},{},{},{},{}", name, register.min.value, register.min.index,
+                     register.max.value, register.max.index, register.last(), register.timeline.len() - 1)?;
+        }
+        Ok(())
+    }
+
+    /// `instruction,register,value` for every point of every timeline, by instruction.
+    pub fn write_timeline_csv<W: Write>(self: &Self, mut output: W) -> io::Result<()>
+    {
+        let mut rows = self.registers.iter()
+            .flat_map(|(name, register)| register.timeline.iter().map(move |&(index, value)| (index, name, value)))
+            .collect::<Vec<_>>();
+        rows.sort();
+        writeln!(output, "instruction,register,value")?;
+        for (index, name, value) in rows
+        {
+            writeln!(output, "{},{},{}", index, name, value)?;
+        }
+        Ok(())
+    }
+
+    /// `instruction,line,condition,taken` for every instruction of `program`.
+    pub fn write_conditions_csv<W: Write>(self: &Self, program: &[Instruction], mut output: W) -> io::Result<()>
+    {
+        writeln!(output, "instruction,line,condition,taken")?;
+        for (index, instruction) in program.iter().enumerate()
+        {
+            let taken = self.taken.get(index).cloned().unwrap_or(0);
+            writeln!(output, "{},{},{},{}", index, instruction.span.line, instruction.condition, taken)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use super::super::parse_program;
+
+    #[test]
+    fn test_history()
+    {
+        let program = parse_program("b inc 5 if a > 1\na inc 1 if b < 5\nc dec -10 if a >= 1\nc inc -20 if c == 10").unwrap();
+        let mut regs = Registers::new();
+        let history = History::run(&program, &mut regs).unwrap();
+
+        assert_eq!(history.taken, vec![0, 1, 1, 1]);
+        assert_eq!(history.registers["c"].timeline, vec![(2, 10), (3, -10)]);
+        assert_eq!(history.registers["c"].min, Extreme { value: -10, index: 3 });
+        assert_eq!(history.peak(), Some(("c", Extreme { value: 10, index: 2 })));
+
+        let mut csv = Vec::new();
+        history.write_registers_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "register,min,min_at,max,max_at,last,changes\n\
+                                                     a,0,0,1,1,1,1\nb,0,1,0,1,0,0\nc,-10,3,10,2,-10,1\n");
+        let mut csv = Vec::new();
+        history.write_timeline_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "instruction,register,value\n0,a,0\n1,a,1\n1,b,0\n2,c,10\n3,c,-10\n");
+        let mut csv = Vec::new();
+        history.write_conditions_csv(&program, &mut csv).unwrap();
+        assert!(String::from_utf8(csv).unwrap().ends_with("2,3,a >= 1,1\n3,4,c == 10,1\n"));
+    }
+}
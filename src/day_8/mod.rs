@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use error::Error;
+use solution::Solution;
+
+mod ast;
+mod compiler;
+mod history;
+
+pub use self::ast::{Action, Comparison, Condition, Instruction, Span, Spanned, Width, parse_program, parse_program_as, print};
+pub use self::compiler::{Compiled, compile};
+pub use self::history::{Extreme, History, RegisterHistory};
+
+pub struct Day8;
+
+impl Solution for Day8
+{
+    type Input = Vec<Instruction>;
+
+    fn day(&self) -> u32
+    {
+        8
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "I Heard You Like Registers"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "instructions.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<Instruction>, Error>
+    {
+        parse_program(input)
+    }
+
+    fn first_puzzle(&self, instrs: &Vec<Instruction>) -> Result<String, Error>
+    {
+        let mut regs = Registers::new();
+        run(instrs, &mut regs)?;
+
+        regs.max()
+            .map(|max| format!("{}", max))
+            .ok_or_else(|| Error::no_solution("no registers were used"))
+    }
+
+    fn second_puzzle(&self, instrs: &Vec<Instruction>) -> Result<String, Error>
+    {
+        let mut regs = Registers::new();
+        History::run(instrs, &mut regs)?
+            .peak()
+            .map(|(_, max)| format!("{}", max.value))
+            .ok_or_else(|| Error::no_solution("no registers were used"))
+    }
+}
+
+pub struct Registers
+{
+    registers: HashMap<String, i128>,
+    width: Width,
+}
+
+impl Registers
+{
+    pub fn new() -> Registers
+    {
+        Registers::with_width(Width::I32)
+    }
+
+    /// Registers holding values in `width`, instructions leaving it fail.
+    pub fn with_width(width: Width) -> Registers
+    {
+        Registers{
+            registers: HashMap::new(),
+            width,
+        }
+    }
+
+    pub fn width(self: &Self) -> Width
+    {
+        self.width
+    }
+
+    /// Register called `name`, which counts as used from then on.
+    pub fn get(self: &mut Self, name: &str) -> &mut i128
+    {
+        self.registers.entry(String::from(name)).or_insert(0)
+    }
+
+    /// Value of the register called `name`, 0 if it was never used.
+    pub fn value(self: &Self, name: &str) -> i128
+    {
+        self.registers.get(name).cloned().unwrap_or(0)
+    }
+
+    /// Largest value among the registers used so far.
+    pub fn max(self: &Self) -> Option<i128>
+    {
+        self.registers.values().cloned().max()
+    }
+}
+
+impl Default for Registers
+{
+    fn default() -> Registers
+    {
+        Registers::new()
+    }
+}
+
+impl Condition
+{
+    /// Reading the register marks it as used, as in the puzzle.
+    pub fn holds(self: &Self, regs: &mut Registers) -> bool
+    {
+        let value = *regs.get(&self.register.node);
+        self.comparison.node.holds(value, self.value.node)
+    }
+}
+
+impl Instruction
+{
+    /// Applies the instruction if its condition holds, returns whether it did.
+    /// Errors, leaving the target unchanged, when the result does not fit the width of `regs`.
+    pub fn execute(self: &Self, regs: &mut Registers) -> Result<bool, Error>
+    {
+        if !self.condition.holds(regs)
+        {
+            return Ok(false);
+        }
+
+        let width = regs.width();
+        let target = regs.get(&self.target.node);
+        let value = match self.action.node
+        {
+            Action::Inc => target.checked_add(self.amount.node),
+            Action::Dec => target.checked_sub(self.amount.node),
+        };
+        match value.filter(|&value| width.contains(value))
+        {
+            Some(value) => *target = value,
+            None => return Err(Error::invalid_state(format!("line {}: {} overflow: {}", self.span.line, width, self))),
+        }
+        Ok(true)
+    }
+}
+
+/// Runs every instruction of `program` once, in order, stopping at the first overflow.
+pub fn run(program: &[Instruction], regs: &mut Registers) -> Result<(), Error>
+{
+    for instruction in program
+    {
+        instruction.execute(regs)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use std::cmp;
+    use super::*;
+
+    #[test]
+    fn first_puzzle() 
+    {
+        let mut regs = Registers::new();
+        let instrs = [
+            Instruction::parse("b inc 5 if a > 1").unwrap(),
+            Instruction::parse("a inc 1 if b < 5").unwrap(),
+            Instruction::parse("c dec -10 if a >= 1").unwrap(),
+            Instruction::parse("c inc -20 if c == 10").unwrap(),
+        ];
+
+        for inst in instrs.iter()
+        {
+            inst.execute(&mut regs).unwrap();
+        }
+
+        assert_eq!(*regs.get("a"), 1);
+        assert_eq!(*regs.get("b"), 0);
+        assert_eq!(*regs.get("c"), -10);
+        assert_eq!(regs.max(), Some(1));
+    }
+
+    #[test]
+    fn second_puzzle() 
+    {
+        let mut regs = Registers::new();
+        let instrs = [
+            Instruction::parse("b inc 5 if a > 1").unwrap(),
+            Instruction::parse("a inc 1 if b < 5").unwrap(),
+            Instruction::parse("c dec -10 if a >= 1").unwrap(),
+            Instruction::parse("c inc -20 if c == 10").unwrap(),
+        ];
+
+        let mut max = i128::MIN;
+        for inst in instrs.iter()
+        {
+            inst.execute(&mut regs).unwrap();
+            max = cmp::max(max, regs.max().unwrap());
+        }
+
+        assert_eq!(max, 10);
+    }
+
+    #[test]
+    fn overflow()
+    {
+        let program = parse_program("a inc 2147483647 if b == 0\nb inc 1 if a > 0\na inc 1 if b == 1\nb dec 1 if a > 0").unwrap();
+        let mut regs = Registers::new();
+        assert_eq!(run(&program, &mut regs).unwrap_err().to_string(),
+                   "invalid state: line 3: i32 overflow: a inc 1 if b == 1");
+        assert_eq!((regs.value("a"), regs.value("b")), (2147483647, 1));
+
+        let mut regs = Registers::with_width(Width::I64);
+        run(&program, &mut regs).unwrap();
+        assert_eq!((regs.value("a"), regs.value("b")), (2147483648, 0));
+    }
+}
\ No newline at end of file
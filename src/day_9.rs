@@ -1,31 +1,78 @@
-use std::io::prelude::*; 
-use std::fs::File; 
+use error::Error;
+use solution::Solution;
 
-pub fn first_puzzle() -> String
-{
-    let stream = read_stream("stream.txt");
-    format!("{}", score(&stream, 1).0)
-}
+pub struct Day9;
 
-pub fn second_puzzle() -> String
+impl Solution for Day9
 {
-    let stream = read_stream("stream.txt");
-    format!("{}", score(&stream, 1).1)
+    type Input = String;
+
+    fn day(&self) -> u32
+    {
+        9
+    }
+
+    fn name(&self) -> &'static str
+    {
+        "Stream Processing"
+    }
+
+    fn input_path(&self) -> &'static str
+    {
+        "stream.txt"
+    }
+
+    fn parse(&self, input: &str) -> Result<String, Error>
+    {
+        let stream = input.trim();
+        validate(stream).map_err(|e| e.within(input, stream))?;
+        Ok(String::from(stream))
+    }
+
+    fn first_puzzle(&self, stream: &String) -> Result<String, Error>
+    {
+        Ok(format!("{}", score(stream, 1)?.0))
+    }
+
+    fn second_puzzle(&self, stream: &String) -> Result<String, Error>
+    {
+        Ok(format!("{}", score(stream, 1)?.1))
+    }
 }
 
-fn read_stream(path: &str) -> String
+fn validate(stream: &str) -> Result<(), Error>
 {
-    let mut file = File::open(path).expect("Failed to open stream file"); 
-    let mut stream = String::new();
-    file.read_to_string(&mut stream).expect("Failed to read stream file");
-    stream
+    let mut groups = Vec::new();
+    let mut garbage = None;
+    let mut ignore = false;
+    for (idx, ch) in stream.char_indices()
+    {
+        match (garbage, ch)
+        {
+            (Some(_), _) if ignore => ignore = false,
+            (Some(_), '!')         => ignore = true,
+            (Some(_), '>')         => garbage = None,
+            (Some(_), _)           => {},
+            (None, '<')            => garbage = Some(idx),
+            (None, '{')            => groups.push(idx),
+            (None, '}')            => { groups.pop(); },
+            (None, _)              => {}
+        }
+    }
+
+    match (garbage, groups.last())
+    {
+        (Some(idx), _) => Err(Error::parse(1, idx + 1, "unmatched <")),
+        (None, Some(&idx)) => Err(Error::parse(1, idx + 1, "unmatched {")),
+        (None, None) => Ok(())
+    }
 }
 
 fn match_brackets_curly(slice: &str) -> Option<usize>
 {
     let mut count = 1;
     let mut ignore = 0;
-    for (idx, ch) in slice.chars().enumerate().skip(1)
+    for (idx, ch) in slice.char_indices().skip(1)
     {
         match ch
         {
@@ -50,7 +97,7 @@ fn match_brackets_angle(slice: &str) -> Option<(usize, u32)>
 {
     let mut trash = 0;
     let mut ignore = false;
-    for (idx, ch) in slice.chars().enumerate().skip(1)
+    for (idx, ch) in slice.char_indices().skip(1)
     {
         match ch
         {
@@ -64,7 +111,8 @@ fn match_brackets_angle(slice: &str) -> Option<(usize, u32)>
     None
 }
 
-fn score(stream: &str, point_per_group: u32) -> (u32, u32)
+/// Score and garbage count of `stream`, every group inside it worth one point more than its parent.
+fn score(stream: &str, point_per_group: u32) -> Result<(u32, u32), Error>
 {
     let mut sum = 0;
     let mut trash = 0;
@@ -79,13 +127,14 @@ fn score(stream: &str, point_per_group: u32) -> (u32, u32)
                 {
                     skip_idx = start + offset + 1;
                     sum += point_per_group;
-                    let (more_sum, more_trash) = score(&stream[start + 1 .. start + offset], point_per_group + 1);
+                    let inner = &stream[start + 1 .. start + offset];
+                    let (more_sum, more_trash) = score(inner, point_per_group + 1).map_err(|e| e.within(stream, inner))?;
                     sum += more_sum;
                     trash += more_trash;
                 } 
                 else
                 {
-                    panic!("unmatched {{: {}", &stream[start..]);
+                    return Err(Error::at_token(1, stream, &stream[start ..], "unmatched {"));
                 }
             }, 
             (start, '<') if idx >= skip_idx => 
@@ -97,14 +146,14 @@ fn score(stream: &str, point_per_group: u32) -> (u32, u32)
                 }
                 else
                 {
-                    panic!("unmatched <: {}", &stream[start ..]);
+                    return Err(Error::at_token(1, stream, &stream[start ..], "unmatched <"));
                 }
             },
             _ => {}
         }
     }
 
-    (sum, trash)
+    Ok((sum, trash))
 }
 
 #[cfg(test)]
@@ -133,12 +182,25 @@ mod tests
         assert_eq!(Some(28), match_brackets_curly("{{<a!>},{<a!>},{<a!>},{<ab>}}"));
     }
 
+    #[test]
+    fn test_validate()
+    {
+        assert!(validate("{{<!>},{<a>}}").is_ok());
+        assert!(validate("{é<a>}").is_ok());
+        assert_eq!("parse error at 1:1: unmatched {", format!("{}", validate("{{<a>}").unwrap_err()));
+        assert_eq!("parse error at 1:3: unmatched <", format!("{}", validate("{{<!>}}").unwrap_err()));
+    }
+
     #[test]
     fn test_score()
     {
-        assert_eq!((3, 17), score("{{<a!>},{<a!>},{<a!>},{<ab>}}", 1));
-        assert_eq!((9, 0), score("{{<!!>},{<!!>},{<!!>},{<!!>}}", 1));
-        assert_eq!((9, 8), score("{{<ab>},{<ab>},{<ab>},{<ab>}}", 1));
-        assert_eq!((1, 4), score("{<a>,<a>,<a>,<a>}", 1));
+        assert_eq!((3, 17), score("{{<a!>},{<a!>},{<a!>},{<ab>}}", 1).unwrap());
+        assert_eq!((9, 0), score("{{<!!>},{<!!>},{<!!>},{<!!>}}", 1).unwrap());
+        assert_eq!((9, 8), score("{{<ab>},{<ab>},{<ab>},{<ab>}}", 1).unwrap());
+        assert_eq!((1, 4), score("{<a>,<a>,<a>,<a>}", 1).unwrap());
+        assert_eq!((3, 2), score("{é<a>{<ü>}}", 1).unwrap());
+        assert_eq!(Some(12), match_brackets_curly("{é<a>{<ü>}}"));
+        assert_eq!("parse error at 1:3: unmatched <", format!("{}", score("{{<a}}", 1).unwrap_err()));
+        assert_eq!("parse error at 1:1: unmatched {", format!("{}", score("{<>,{}", 1).unwrap_err()));
     }
 }
@@ -0,0 +1,111 @@
+use std::error;
+use std::fmt;
+use std::io;
+use day_18::Fault;
+
+/// Error raised while reading, parsing or solving a puzzle.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// Malformed input, `line` and `column` are 1-based.
+    Parse { line: usize, column: usize, message: String },
+    InvalidState(String),
+    /// Arithmetic error of a day 18 or day 23 program.
+    Fault(Fault),
+    NoSolution(String),
+}
+
+impl Error {
+    pub fn parse<M: Into<String>>(line: usize, column: usize, message: M) -> Error {
+        Error::Parse { line, column, message: message.into() }
+    }
+
+    /// Parse error pointing at `token`, which has to be a slice of `line`.
+    pub fn at_token<M: Into<String>>(line_no: usize, line: &str, token: &str, message: M) -> Error {
+        Error::parse(line_no, column(line, token), message)
+    }
+
+    pub fn invalid_state<M: Into<String>>(message: M) -> Error {
+        Error::InvalidState(message.into())
+    }
+
+    pub fn no_solution<M: Into<String>>(message: M) -> Error {
+        Error::NoSolution(message.into())
+    }
+
+    /// Moves a parse error produced for a single line to `line_no` of the whole input.
+    pub fn on_line(self, line_no: usize) -> Error {
+        match self {
+            Error::Parse { column, message, .. } => Error::Parse { line: line_no, column, message },
+            other => other,
+        }
+    }
+
+    /// Moves a parse error produced for `inner` to its position within `outer`.
+    pub fn within(self, outer: &str, inner: &str) -> Error {
+        match self {
+            Error::Parse { line, column: col, message } => {
+                Error::Parse { line, column: col + column(outer, inner) - 1, message }
+            },
+            other => other,
+        }
+    }
+}
+
+/// 1-based column of `token` within `line`, or 1 if it is not a slice of it.
+pub fn column(line: &str, token: &str) -> usize {
+    let start = line.as_ptr() as usize;
+    let offset = (token.as_ptr() as usize).wrapping_sub(start);
+    if offset <= line.len() {
+        offset + 1
+    }
+    else {
+        1
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Parse { line, column, message } => write!(f, "parse error at {}:{}: {}", line, column, message),
+            Error::InvalidState(message) => write!(f, "invalid state: {}", message),
+            Error::Fault(fault) => write!(f, "fault: {}", fault),
+            Error::NoSolution(message) => write!(f, "no solution: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column() {
+        let line = "b inc 5 if a > 1";
+        let token = line.split_whitespace().nth(2).unwrap();
+        assert_eq!(column(line, token), 7);
+
+        let err = Error::at_token(1, line, token, "bad").on_line(4);
+        assert_eq!(format!("{}", err), "parse error at 4:7: bad");
+
+        let outer = format!("a {}", line);
+        let err = Error::at_token(1, line, token, "bad").within(&outer, &outer[2 ..]);
+        assert_eq!(format!("{}", err), "parse error at 1:9: bad");
+    }
+}
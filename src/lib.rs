@@ -1,4 +1,8 @@
-pub mod simple_error;
+// Methods spell out `self: &Self` throughout the crate.
+#![allow(clippy::needless_arbitrary_self_type)]
+
+pub mod error;
+pub mod solution;
 
 pub mod day_1;
 pub mod day_2;
@@ -24,4 +28,94 @@ pub mod day_21;
 pub mod day_22;
 pub mod day_23;
 pub mod day_24;
-pub mod day_25;
\ No newline at end of file
+pub mod day_25;
+
+pub use error::Error;
+pub use solution::Solution;
+pub use solution::Puzzle;
+pub use solution::Report;
+
+static CALENDAR: [&'static dyn Puzzle; 25] = [
+    &day_1::Day1,
+    &day_2::Day2,
+    &day_3::Day3,
+    &day_4::Day4,
+    &day_5::Day5,
+    &day_6::Day6,
+    &day_7::Day7,
+    &day_8::Day8,
+    &day_9::Day9,
+    &day_10::Day10,
+    &day_11::Day11,
+    &day_12::Day12,
+    &day_13::Day13,
+    &day_14::Day14,
+    &day_15::Day15,
+    &day_16::Day16,
+    &day_17::Day17,
+    &day_18::Day18,
+    &day_19::Day19,
+    &day_20::Day20,
+    &day_21::Day21,
+    &day_22::Day22,
+    &day_23::Day23,
+    &day_24::Day24,
+    &day_25::Day25,
+];
+
+/// All registered days, ordered by day number.
+pub fn calendar() -> &'static [&'static dyn Puzzle] {
+    &CALENDAR
+}
+
+pub fn find(day: u32) -> Option<&'static dyn Puzzle> {
+    CALENDAR.iter().cloned().find(|puzzle| puzzle.day() == day)
+}
+
+/// Days selected by `all`, a single day (`7` or `day_7`) or an inclusive range (`1..10`).
+pub fn select(spec: &str) -> Option<Vec<&'static dyn Puzzle>> {
+    let day = |s: &str| s.trim_start_matches("day_").parse::<u32>().ok();
+    if spec == "all" {
+        return Some(CALENDAR.to_vec());
+    }
+
+    let (first, last) = match spec.find("..") {
+        Some(idx) => (day(&spec[.. idx])?, day(spec[idx + 2 ..].trim_start_matches('='))?),
+        None => (day(spec)?, day(spec)?),
+    };
+    let selected: Vec<_> = CALENDAR.iter().cloned()
+        .filter(|puzzle| first <= puzzle.day() && puzzle.day() <= last)
+        .collect();
+
+    if selected.is_empty() {
+        None
+    }
+    else {
+        Some(selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calendar() {
+        for (idx, puzzle) in calendar().iter().enumerate() {
+            assert_eq!(puzzle.day(), idx as u32 + 1);
+        }
+        assert_eq!(find(7).map(|p| p.name()), Some("Recursive Circus"));
+        assert!(find(26).is_none());
+    }
+
+    #[test]
+    fn test_select() {
+        let days = |spec| select(spec).map(|s| s.iter().map(|p| p.day()).collect::<Vec<_>>());
+        assert_eq!(days("all").map(|d| d.len()), Some(25));
+        assert_eq!(days("day_7"), Some(vec![7]));
+        assert_eq!(days("3..5"), Some(vec![3, 4, 5]));
+        assert_eq!(days("24..30"), Some(vec![24, 25]));
+        assert_eq!(days("26"), None);
+        assert_eq!(days("x..5"), None);
+    }
+}
@@ -1,141 +1,509 @@
 use std::env::args;
+use std::fs::File;
+use std::io::{self, Read};
+use std::time::Duration;
 extern crate calendar;
 
-fn main() 
+fn read_input(path: &str) -> io::Result<String>
 {
-    if let Some(day) = args().nth(1) {
-        match day.as_str() {
-            "day_1" => {
-                println!("day_1");
-                println!("first_puzzle: {}", calendar::day_1::first_puzzle());
-                println!("second_puzzle: {}",calendar::day_1::second_puzzle());
-            },
-            "day_2" => {
-                println!("day_2");
-                println!("first_puzzle: {}", calendar::day_2::first_puzzle());
-                println!("second_puzzle: {}",calendar::day_2::second_puzzle());
-            },
-            "day_3" => {
-                println!("day_3");
-                println!("first_puzzle: {}", calendar::day_3::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_3::second_puzzle());
-            },
-            "day_4" => {
-                println!("day_4");
-                println!("first_puzzle: {}", calendar::day_4::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_4::second_puzzle());
-            },
-            "day_5" => {
-                println!("day_5");
-                println!("first_puzzle: {}", calendar::day_5::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_5::second_puzzle());
-            },
-            "day_6" => {
-                println!("day_6");
-                println!("first_puzzle: {}", calendar::day_6::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_6::second_puzzle());
-            },
-            "day_7" => {
-                println!("day_7");
-                println!("first_puzzle: {}", calendar::day_7::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_7::second_puzzle());
-            },
-            "day_8" => {
-                println!("day_8");
-                println!("first_puzzle: {}", calendar::day_8::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_8::second_puzzle());
-            },
-            "day_9" => {
-                println!("day_9");
-                println!("first_puzzle: {}", calendar::day_9::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_9::second_puzzle());
-            },
-            "day_10" => {
-                println!("day_10");
-                println!("first_puzzle: {}", calendar::day_10::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_10::second_puzzle());
-            },
-            "day_11" => {
-                println!("day_11");
-                println!("first_puzzle: {}", calendar::day_11::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_11::second_puzzle());
-            },
-            "day_12" => {
-                println!("day_12");
-                println!("first_puzzle: {}", calendar::day_12::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_12::second_puzzle());
-            },
-            "day_13" => {
-                println!("day_13");
-                println!("first_puzzle: {}", calendar::day_13::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_13::second_puzzle());
-            },
-            "day_14" => {
-                println!("day_14");
-                println!("first_puzzle: {}", calendar::day_14::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_14::second_puzzle());
-            },
-            "day_15" => {
-                println!("day_15");
-                println!("first_puzzle: {}", calendar::day_15::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_15::second_puzzle());
-            },
-            "day_16" => {
-                println!("day_16");
-                println!("first_puzzle: {}", calendar::day_16::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_16::second_puzzle());
-            },
-            "day_17" => {
-                println!("day_17");
-                println!("first_puzzle: {}", calendar::day_17::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_17::second_puzzle());
-            },
-            "day_18" => {
-                println!("day_18");
-                println!("first_puzzle: {}", calendar::day_18::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_18::second_puzzle());
-            },
-            "day_19" => {
-                println!("day_19");
-                println!("first_puzzle: {}", calendar::day_19::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_19::second_puzzle());
-            },
-            "day_20" => {
-                println!("day_20");
-                println!("first_puzzle: {}", calendar::day_20::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_20::second_puzzle());
-            },
-            "day_21" => {
-                println!("day_21");
-                println!("first_puzzle: {}", calendar::day_21::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_21::second_puzzle());
-            },
-            "day_22" => {
-                println!("day_22");
-                println!("first_puzzle: {}", calendar::day_22::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_22::second_puzzle());
+    let mut input = String::new();
+    if path == "-" {
+        io::stdin().read_to_string(&mut input)?;
+    }
+    else {
+        File::open(path)?.read_to_string(&mut input)?;
+    }
+    Ok(input)
+}
+
+fn millis(duration: Duration) -> String
+{
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+fn run_table(puzzles: &[&dyn calendar::Puzzle])
+{
+    println!("{:<6} {:<34} {:<34} {:>10} {:>10} {:>10}",
+             "day", "first_puzzle", "second_puzzle", "parse ms", "first ms", "second ms");
+
+    let mut total = Duration::default();
+    for puzzle in puzzles {
+        let input = match read_input(puzzle.input_path()) {
+            Ok(input) => input,
+            Err(error) => {
+                println!("day_{:<2} failed to read {}: {}", puzzle.day(), puzzle.input_path(), error);
+                continue;
+            }
+        };
+
+        let report = match puzzle.run(&input) {
+            Ok(report) => report,
+            Err(error) => {
+                println!("day_{:<2} {}", puzzle.day(), error);
+                continue;
+            }
+        };
+        total += report.total_time();
+        println!("day_{:<2} {:<34} {:<34} {:>10} {:>10} {:>10}",
+                 puzzle.day(), report.first, report.second,
+                 millis(report.parse_time), millis(report.first_time), millis(report.second_time));
+    }
+    println!("{:<6} {:>102} ms", "total", millis(total));
+}
+
+/// Loads the program at `path` in the dialect given by `--dialect`, or the one it looks written in,
+/// with the arithmetic given by `--overflow` and `--modulo`.
+fn load_assembler(path: &str, options: &[String]) -> Option<calendar::day_18::Assembler>
+{
+    let text = match read_input(path) {
+        Ok(text) => text,
+        Err(error) => {
+            println!("Failed to read {}: {}", path, error);
+            return None;
+        }
+    };
+
+    let dialect = match options.iter().position(|option| option == "--dialect") {
+        Some(index) => match options.get(index + 1).and_then(|name| calendar::day_18::Dialect::parse(name)) {
+            Some(dialect) => dialect,
+            None => {
+                println!("Expected sound, duet or coprocessor after --dialect");
+                return None;
+            }
+        },
+        None => match calendar::day_18::assemble(&text) {
+            Ok(program) => calendar::day_18::Dialect::detect(&program),
+            Err(diagnostics) => {
+                println!("{}", diagnostics);
+                return None;
+            }
+        },
+    };
+
+    let mut arithmetic = calendar::day_18::Arithmetic::default();
+    if let Some(index) = options.iter().position(|option| option == "--overflow") {
+        match options.get(index + 1).and_then(|name| calendar::day_18::Overflow::parse(name)) {
+            Some(overflow) => arithmetic.overflow = overflow,
+            None => {
+                println!("Expected checked, wrapping or saturating after --overflow");
+                return None;
+            }
+        }
+    }
+    if let Some(index) = options.iter().position(|option| option == "--modulo") {
+        match options.get(index + 1).and_then(|name| calendar::day_18::Modulo::parse(name)) {
+            Some(modulo) => arithmetic.modulo = modulo,
+            None => {
+                println!("Expected remainder or euclidean after --modulo");
+                return None;
+            }
+        }
+    }
+
+    let mut assembler = calendar::day_18::Assembler::new(0, dialect);
+    assembler.set_arithmetic(arithmetic);
+    match assembler.load_program_str(&text) {
+        Ok(()) => Some(assembler),
+        Err(diagnostics) => {
+            println!("{}", diagnostics);
+            None
+        }
+    }
+}
+
+fn debug(path: &str, options: &[String])
+{
+    if let Some(assembler) = load_assembler(path, options) {
+        println!("{} {} instructions loaded, type help for the commands", assembler.instructions.len(), assembler.dialect());
+        let mut debugger = calendar::day_18::Debugger::new(assembler);
+        if let Some(index) = options.iter().position(|option| option == "--limit") {
+            match options.get(index + 1).and_then(|limit| limit.parse().ok()) {
+                Some(limit) => debugger.set_limit(limit),
+                None => {
+                    println!("Missing instruction count after --limit");
+                    return;
+                }
+            }
+        }
+        let stdin = io::stdin();
+        if let Err(error) = debugger.run(stdin.lock(), io::stdout()) {
+            println!("Error: {}", error);
+        }
+    }
+}
+
+fn profile(path: &str, options: &[String])
+{
+    let mut assembler = match load_assembler(path, options) {
+        Some(assembler) => assembler,
+        None => return,
+    };
+
+    let mut limit = 10_000_000;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        if option == "--dialect" || option == "--overflow" || option == "--modulo" {
+            options.next();
+            continue;
+        }
+        if option == "--optimize" {
+            assembler.optimize();
+            continue;
+        }
+        if option == "--limit" {
+            match options.next().and_then(|limit| limit.parse().ok()) {
+                Some(value) => limit = value,
+                None => {
+                    println!("Missing instruction count after --limit");
+                    return;
+                }
+            }
+            continue;
+        }
+
+        let mut parts = option.splitn(2, '=');
+        let reg = parts.next().and_then(calendar::day_18::named_register);
+        match (reg, parts.next().and_then(|value| value.parse().ok())) {
+            (Some(reg), Some(value)) => assembler.registers[reg] = value,
+            _ => {
+                println!("Unrecognized option: {}, expected --limit <n>, --dialect <name>, --overflow <policy>, --modulo <name>, --optimize or <register>=<value>", option);
+                return;
+            }
+        }
+    }
+
+    assembler.enable_profile();
+    if let Err(error) = assembler.run(limit) {
+        println!("Error: {}", error);
+    }
+    if let Some(profile) = assembler.profile() {
+        println!("{}", profile.report(&assembler.instructions, 5));
+    }
+}
+
+fn trace(path: &str, output: &str, options: &[String])
+{
+    let mut assembler = match load_assembler(path, options) {
+        Some(assembler) => assembler,
+        None => return,
+    };
+    let limit = match options.iter().position(|option| option == "--limit") {
+        Some(index) => match options.get(index + 1).and_then(|limit| limit.parse().ok()) {
+            Some(limit) => limit,
+            None => {
+                println!("Missing instruction count after --limit");
+                return;
+            }
+        },
+        None => 10_000_000,
+    };
+
+    assembler.enable_trace();
+    if let Err(error) = assembler.run(limit) {
+        println!("Error: {}", error);
+    }
+    if let Some(trace) = assembler.take_trace() {
+        match File::create(output).and_then(|file| trace.write(io::BufWriter::new(file))) {
+            Ok(()) => println!("{} steps written to {}", trace.steps.len(), output),
+            Err(error) => println!("Failed to write {}: {}", output, error),
+        }
+    }
+}
+
+fn replay(path: &str, command: &[String])
+{
+    let trace = match File::open(path).map_err(calendar::error::Error::from).and_then(|file| calendar::day_18::Trace::read(io::BufReader::new(file))) {
+        Ok(trace) => trace,
+        Err(error) => {
+            println!("Failed to read {}: {}", path, error);
+            return;
+        }
+    };
+    let replay = calendar::day_18::Replay::new(&trace);
+    let numbers = command.iter().skip(1).map(|arg| arg.parse::<usize>().ok()).collect::<Vec<_>>();
+    let name = calendar::day_18::register_name;
+
+    match (command.first().map(String::as_str), numbers.as_slice()) {
+        (Some("seek"), &[Some(step)]) => match replay.seek(step) {
+            Some(frame) => {
+                let location = match (frame.ic, &trace.end) {
+                    (Some(ic), _) => format!("next ic {}", ic),
+                    (None, Some(end)) => end.to_string(),
+                    (None, None) => String::from("end of trace"),
+                };
+                println!("step {}: {}, {}", frame.step, location, frame.registers);
             },
-            "day_23" => {
-                println!("day_23");
-                println!("first_puzzle: {}", calendar::day_23::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_23::second_puzzle());
+            None => println!("The trace only has {} steps", replay.len()),
+        },
+        (Some("diff"), &[Some(first), Some(second)]) => match replay.diff(first, second) {
+            Some(changes) => {
+                for (reg, lhs, rhs) in changes {
+                    println!("{}: {} -> {}", name(reg), lhs, rhs);
+                }
             },
-            "day_24" => {
-                println!("day_24");
-                println!("first_puzzle: {}", calendar::day_24::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_24::second_puzzle());
+            None => println!("The trace only has {} steps", replay.len()),
+        },
+        (Some("find"), _) if command.len() == 3 => {
+            let reg = calendar::day_18::named_register(&command[1]);
+            match (reg, command[2].parse()) {
+                (Some(reg), Ok(value)) => match replay.find(reg, value) {
+                    Some(step) => println!("{} = {} after step {}", command[1], value, step),
+                    None => println!("{} never holds {}", command[1], value),
+                },
+                _ => println!("Expected find <register> <value>"),
+            }
+        },
+        _ => println!("Expected replay <trace> seek <step>|diff <step> <step>|find <register> <value>"),
+    }
+}
+
+fn load_instructions(path: &str, options: &[String]) -> Option<(Vec<calendar::day_8::Instruction>, calendar::day_8::Width)>
+{
+    let width = match options.iter().position(|option| option == "--width") {
+        Some(index) => match options.get(index + 1).and_then(|name| calendar::day_8::Width::parse(name)) {
+            Some(width) => width,
+            None => {
+                println!("Expected i32, i64 or i128 after --width");
+                return None;
+            }
+        },
+        None => calendar::day_8::Width::default(),
+    };
+    match read_input(path).map(|text| calendar::day_8::parse_program_as(&text, width)) {
+        Ok(Ok(program)) => Some((program, width)),
+        Ok(Err(error)) => {
+            println!("Error: {}", error);
+            None
+        },
+        Err(error) => {
+            println!("Failed to read {}: {}", path, error);
+            None
+        }
+    }
+}
+
+fn compile(path: &str, options: &[String])
+{
+    match load_instructions(path, options).map(|(program, _)| calendar::day_8::compile(&program)) {
+        Some(Ok(compiled)) => {
+            for (name, &reg) in &compiled.registers {
+                eprintln!("{} = {}", name, calendar::day_18::register_name(reg));
+            }
+            for instruction in &compiled.instructions {
+                println!("{}", instruction);
+            }
+        },
+        Some(Err(error)) => println!("Error: {}", error),
+        None => (),
+    }
+}
+
+fn history(path: &str, table: &str, options: &[String])
+{
+    let (program, width) = match load_instructions(path, options) {
+        Some(loaded) => loaded,
+        None => return,
+    };
+    let history = match calendar::day_8::History::run(&program, &mut calendar::day_8::Registers::with_width(width)) {
+        Ok(history) => history,
+        Err(error) => {
+            println!("Error: {}", error);
+            return;
+        }
+    };
+    let stdout = io::stdout();
+    let written = match table {
+        "registers" => history.write_registers_csv(stdout.lock()),
+        "timeline" => history.write_timeline_csv(stdout.lock()),
+        "conditions" => history.write_conditions_csv(&program, stdout.lock()),
+        _ => {
+            println!("Expected history <instructions> registers|timeline|conditions [--width i32|i64|i128]");
+            return;
+        }
+    };
+    if let Err(error) = written {
+        println!("Failed to write {}: {}", table, error);
+    }
+}
+
+fn transpile(path: &str, options: &[String])
+{
+    let name = match options.iter().position(|option| option == "--name") {
+        Some(index) => match options.get(index + 1) {
+            Some(name) => name.as_str(),
+            None => {
+                println!("Missing function name after --name");
+                return;
+            }
+        },
+        None => "program",
+    };
+    if let Some(assembler) = load_assembler(path, options) {
+        print!("{}", calendar::day_18::transpile(&assembler.instructions, name, assembler.arithmetic()));
+    }
+}
+
+fn network(path: &str, options: &[String])
+{
+    let threads = options.iter().any(|option| option == "--threads");
+    let limit_at = options.iter().position(|option| option == "--limit");
+    let limit = match limit_at {
+        Some(index) => match options.get(index + 1).and_then(|limit| limit.parse().ok()) {
+            Some(limit) => limit,
+            None => {
+                println!("Missing instruction count after --limit");
+                return;
+            }
+        },
+        None => 10_000_000,
+    };
+    let options = options.iter()
+        .enumerate()
+        .filter(|&(index, option)| option != "--threads" && limit_at.is_none_or(|at| index != at && index != at + 1))
+        .map(|(_, option)| option)
+        .collect::<Vec<_>>();
+    let count = options.first().map_or(Some(2), |count| count.parse::<usize>().ok().filter(|&count| count > 0));
+    let topology = options.get(1).map_or(Some(calendar::day_18::Topology::Pairwise), |name| calendar::day_18::Topology::parse(name));
+    let (count, topology) = match (count, topology) {
+        (Some(count), Some(topology)) => (count, topology),
+        _ => {
+            println!("Expected network <program> [<count>] [pairwise|ring|broadcast] [--threads] [--limit <n>]");
+            return;
+        }
+    };
+
+    match read_input(path).map(|text| (calendar::day_18::assemble(&text), calendar::day_18::source_lines(&text))) {
+        Ok((Ok(program), lines)) => {
+            let network = calendar::day_18::Network::new(&program, count, topology).map(|mut network| {
+                network.set_lines(&lines);
+                network
+            });
+            let report = network.and_then(|mut network| if threads { network.run_threaded(limit) } else { network.run(limit) });
+            match report {
+                Ok(report) => println!("{}", report),
+                Err(error) => println!("Error: {}", error),
+            }
+        },
+        Ok((Err(diagnostics), _)) => println!("{}", diagnostics),
+        Err(error) => println!("Failed to read {}: {}", path, error),
+    }
+}
+
+fn main()
+{
+    let args: Vec<String> = args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("debug") {
+        match args.get(1) {
+            Some(path) => debug(path, &args[2 ..]),
+            None => println!("Missing program after debug"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("compile") {
+        match args.get(1) {
+            Some(path) => compile(path, &args[2 ..]),
+            None => println!("Missing instructions after compile"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("decompile") {
+        match args.get(1).map(|path| (path, read_input(path))) {
+            Some((_, Ok(text))) => match calendar::day_18::assemble(&text) {
+                Ok(program) => print!("{}", calendar::day_18::decompile(&program)),
+                Err(diagnostics) => println!("{}", diagnostics),
             },
-            "day_25" => {
-                println!("day_25");
-                println!("first_puzzle: {}", calendar::day_25::first_puzzle());
-                println!("second_puzzle: {}", calendar::day_25::second_puzzle());
+            Some((path, Err(error))) => println!("Failed to read {}: {}", path, error),
+            None => println!("Missing program after decompile"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("history") {
+        match (args.get(1), args.get(2)) {
+            (Some(path), Some(table)) => history(path, table, &args[3 ..]),
+            _ => println!("Missing instructions or table after history"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("network") {
+        match args.get(1) {
+            Some(path) => network(path, &args[2 ..]),
+            None => println!("Missing program after network"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("profile") {
+        match args.get(1) {
+            Some(path) => profile(path, &args[2 ..]),
+            None => println!("Missing program after profile"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("trace") {
+        match (args.get(1), args.get(2)) {
+            (Some(path), Some(output)) => trace(path, output, &args[3 ..]),
+            _ => println!("Missing program or output after trace"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("transpile") {
+        match args.get(1) {
+            Some(path) => transpile(path, &args[2 ..]),
+            None => println!("Missing program after transpile"),
+        }
+    }
+    else if args.first().map(String::as_str) == Some("replay") {
+        match args.get(1) {
+            Some(path) => replay(path, &args[2 ..]),
+            None => println!("Missing trace after replay"),
+        }
+    }
+    else if let Some(spec) = args.first().filter(|spec| *spec == "all" || spec.contains("..")) {
+        match calendar::select(spec) {
+            Some(puzzles) => run_table(&puzzles),
+            None => println!("Unrecognized days: {}", spec),
+        }
+    }
+    else if let Some(day) = args.first() {
+        let number = day.trim_start_matches("day_").parse::<u32>().ok();
+        match number.and_then(calendar::find) {
+            Some(puzzle) => {
+                let path = match args.iter().position(|arg| arg == "--input") {
+                    Some(index) => match args.get(index + 1) {
+                        Some(path) => path.as_str(),
+                        None => {
+                            println!("Missing path after --input");
+                            return;
+                        }
+                    },
+                    None => puzzle.input_path(),
+                };
+
+                let input = match read_input(path) {
+                    Ok(input) => input,
+                    Err(error) => {
+                        println!("Failed to read {}: {}", path, error);
+                        return;
+                    }
+                };
+
+                println!("day_{}: {}", puzzle.day(), puzzle.name());
+                match puzzle.solve(&input) {
+                    Ok((first, second)) => {
+                        println!("first_puzzle: {}", first);
+                        println!("second_puzzle: {}", second);
+                    },
+                    Err(error) => println!("Error: {}", error),
+                }
             },
-            _ => {
+            None => {
                 println!("Unrecognized day: {}", day);
             }
         }
     }
     else {
-        println!("Usage: calendar.exe day");
+        println!("Usage: calendar.exe day [--input <path>|-]");
+        println!("       calendar.exe all|first..last");
+        println!("       calendar.exe compile <instructions> [--width i32|i64|i128]");
+        println!("       calendar.exe debug <program> [--limit <n>] [--dialect sound|duet|coprocessor] [--overflow checked|wrapping|saturating] [--modulo remainder|euclidean]");
+        println!("       calendar.exe decompile <program>");
+        println!("       calendar.exe history <instructions> registers|timeline|conditions [--width <name>]");
+        println!("       calendar.exe network <program> [<count>] [pairwise|ring|broadcast] [--threads] [--limit <n>]");
+        println!("       calendar.exe profile <program> [--limit <n>] [--dialect <name>] [--overflow <policy>] [--modulo <name>] [--optimize] [<register>=<value>...]");
+        println!("       calendar.exe replay <trace> seek <step>|diff <step> <step>|find <register> <value>");
+        println!("       calendar.exe trace <program> <output> [--limit <n>] [--dialect <name>] [--overflow <policy>] [--modulo <name>]");
+        println!("       calendar.exe transpile <program> [--name <function>] [--dialect <name>] [--overflow <policy>] [--modulo <name>]");
     }
-}
\ No newline at end of file
+}
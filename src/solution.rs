@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+use error::Error;
+
+/// Solution of a single advent calendar day.
+pub trait Solution {
+    type Input;
+
+    fn day(&self) -> u32;
+    fn name(&self) -> &'static str;
+    /// Path of the puzzle input bundled with the crate.
+    fn input_path(&self) -> &'static str;
+    fn parse(&self, input: &str) -> Result<Self::Input, Error>;
+    fn first_puzzle(&self, input: &Self::Input) -> Result<String, Error>;
+    fn second_puzzle(&self, input: &Self::Input) -> Result<String, Error>;
+}
+
+/// Object safe view of a `Solution`, as stored in the calendar registry.
+pub trait Puzzle: Sync {
+    fn day(&self) -> u32;
+    fn name(&self) -> &'static str;
+    fn input_path(&self) -> &'static str;
+    fn solve(&self, input: &str) -> Result<(String, String), Error>;
+    fn run(&self, input: &str) -> Result<Report, Error>;
+}
+
+/// Answers of both parts together with the time spent in each phase.
+pub struct Report {
+    pub first: String,
+    pub second: String,
+    pub parse_time: Duration,
+    pub first_time: Duration,
+    pub second_time: Duration,
+}
+
+impl Report {
+    pub fn total_time(&self) -> Duration {
+        self.parse_time + self.first_time + self.second_time
+    }
+}
+
+impl<S: Solution + Sync> Puzzle for S {
+    fn day(&self) -> u32 {
+        Solution::day(self)
+    }
+
+    fn name(&self) -> &'static str {
+        Solution::name(self)
+    }
+
+    fn input_path(&self) -> &'static str {
+        Solution::input_path(self)
+    }
+
+    fn solve(&self, input: &str) -> Result<(String, String), Error> {
+        let input = self.parse(input)?;
+        Ok((self.first_puzzle(&input)?, self.second_puzzle(&input)?))
+    }
+
+    fn run(&self, input: &str) -> Result<Report, Error> {
+        let start = Instant::now();
+        let input = self.parse(input)?;
+        let parse_time = start.elapsed();
+
+        let start = Instant::now();
+        let first = self.first_puzzle(&input)?;
+        let first_time = start.elapsed();
+
+        let start = Instant::now();
+        let second = self.second_puzzle(&input)?;
+        let second_time = start.elapsed();
+
+        Ok(Report { first, second, parse_time, first_time, second_time })
+    }
+}